@@ -0,0 +1,268 @@
+use crate::applet::Applet;
+use crate::pkcs7app::{pkcs7_pad, pkcs7_unpad};
+use aes::cipher::consts::U16;
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+const AES_BLOCK_LEN: usize = 16;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum AesMode {
+    Ecb,
+    Cbc,
+}
+
+pub struct AesApplet {
+    mode: AesMode,
+    key: Vec<u8>,
+    iv: Vec<u8>,
+    decrypt: bool,
+    no_pad: bool,
+}
+
+impl AesApplet {
+    /* ECB or CBC over whole blocks, val length must be a multiple of the block size */
+    fn crypt<C>(&self, val: &[u8]) -> Vec<u8>
+    where
+        C: KeyInit + BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>,
+    {
+        let cipher = C::new_from_slice(&self.key).unwrap();
+        let mut res = Vec::with_capacity(val.len());
+        let mut prev = self.iv.clone();
+        for chunk in val.chunks_exact(AES_BLOCK_LEN) {
+            let mut block = GenericArray::clone_from_slice(chunk);
+            if self.decrypt {
+                cipher.decrypt_block(&mut block);
+                if self.mode == AesMode::Cbc {
+                    block.iter_mut().zip(&prev).for_each(|(b, p)| *b ^= p);
+                    prev = chunk.to_vec();
+                }
+            } else {
+                if self.mode == AesMode::Cbc {
+                    block.iter_mut().zip(&prev).for_each(|(b, p)| *b ^= p);
+                }
+                cipher.encrypt_block(&mut block);
+                if self.mode == AesMode::Cbc {
+                    prev = block.to_vec();
+                }
+            }
+            res.extend_from_slice(&block);
+        }
+        res
+    }
+}
+
+impl Applet for AesApplet {
+    fn command(&self) -> &'static str {
+        "aes"
+    }
+    fn description(&self) -> &'static str {
+        "AES encryption/decryption in ECB or CBC mode"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-m --mode <mode> "block cipher mode")
+                    .value_parser(clap::builder::EnumValueParser::<AesMode>::new())
+                    .default_value("cbc"),
+            )
+            .arg(arg!(-k --key <HEX> "key, in hex (16, 24 or 32 bytes)").required(true))
+            .arg(arg!(--iv <HEX> "IV, in hex (16 bytes), required for CBC"))
+            .arg(arg!(-d --decrypt "decrypt instead of encrypting"))
+            .arg(arg!(--"no-pad" "do not add or strip PKCS#7 padding"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            mode: AesMode::Cbc,
+            key: vec![],
+            iv: vec![],
+            decrypt: false,
+            no_pad: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let mode = *args.get_one::<AesMode>("mode").unwrap();
+        let key =
+            hex::decode(args.get_one::<String>("key").unwrap()).context("Invalid hex in key")?;
+        if ![16, 24, 32].contains(&key.len()) {
+            bail!(
+                "Invalid key length {}, must be 16, 24 or 32 bytes",
+                key.len()
+            );
+        }
+        let iv = match args.get_one::<String>("iv") {
+            Some(_) if mode == AesMode::Ecb => bail!("ECB mode does not use an IV"),
+            Some(iv) => hex::decode(iv).context("Invalid hex in IV")?,
+            None if mode == AesMode::Cbc => bail!("CBC mode requires an IV (--iv)"),
+            None => vec![],
+        };
+        if mode == AesMode::Cbc && iv.len() != AES_BLOCK_LEN {
+            bail!("Invalid IV length {}, must be 16 bytes", iv.len());
+        }
+        Ok(Box::new(Self {
+            mode,
+            key,
+            iv,
+            decrypt: args.get_flag("decrypt"),
+            no_pad: args.get_flag("no-pad"),
+        }))
+    }
+
+    fn explain(&self) -> String {
+        let mut res = format!(
+            "aes: {} with AES-{} in {}",
+            if self.decrypt { "decrypt" } else { "encrypt" },
+            self.key.len() * 8,
+            match self.mode {
+                AesMode::Ecb => "ECB mode".to_string(),
+                AesMode::Cbc => format!("CBC mode with IV {}", hex::encode(&self.iv)),
+            }
+        );
+        res.push_str(if self.no_pad {
+            ", without padding."
+        } else if self.decrypt {
+            ", stripping PKCS#7 padding."
+        } else {
+            ", adding PKCS#7 padding."
+        });
+        res
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let val = if self.decrypt || self.no_pad {
+            val
+        } else {
+            pkcs7_pad(&val, AES_BLOCK_LEN)
+        };
+        if val.len() % AES_BLOCK_LEN != 0 {
+            bail!(
+                "Input length {} is not a multiple of the block size",
+                val.len()
+            );
+        }
+        let res = match self.key.len() {
+            16 => self.crypt::<aes::Aes128>(&val),
+            24 => self.crypt::<aes::Aes192>(&val),
+            _ => self.crypt::<aes::Aes256>(&val),
+        };
+        if self.decrypt && !self.no_pad {
+            pkcs7_unpad(&res, AES_BLOCK_LEN)
+        } else {
+            Ok(res)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::applet::build_applet;
+
+    /* NIST SP 800-38A, F.1.1 and F.2.1 */
+    const KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const PLAIN: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51";
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["aes", "-k", KEY, "--iv", IV, "--no-pad"])
+            .write_stdin(hex::decode(PLAIN).unwrap())
+            .assert()
+            .stdout(predicates::ord::eq(
+                hex::decode("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2")
+                    .unwrap(),
+            ))
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["aes", "-k", KEY, "abc"])
+            .assert()
+            .stderr(predicates::str::contains("requires an IV"))
+            .failure();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["aes", "-m", "ecb", "-k", "00112233", "abc"])
+            .assert()
+            .stderr(predicates::str::contains("Invalid key length"))
+            .failure();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["aes", "-m", "ecb", "-k", KEY, "--iv", IV, "abc"])
+            .assert()
+            .stderr(predicates::str::contains("ECB mode does not use an IV"))
+            .failure();
+    }
+
+    #[test]
+    fn test_vectors() {
+        let mut aes = AesApplet {
+            mode: AesMode::Cbc,
+            key: hex::decode(KEY).unwrap(),
+            iv: hex::decode(IV).unwrap(),
+            decrypt: false,
+            no_pad: true,
+        };
+        let cbc = hex::decode("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2")
+            .unwrap();
+        assert_eq!(aes.process_test(hex::decode(PLAIN).unwrap()), cbc);
+        aes.decrypt = true;
+        assert_eq!(aes.process_test(cbc), hex::decode(PLAIN).unwrap());
+
+        aes.mode = AesMode::Ecb;
+        aes.decrypt = false;
+        assert_eq!(
+            hex::encode(aes.process_test(hex::decode(PLAIN).unwrap())),
+            "3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf"
+        );
+        assert!(aes.process(b"not aligned".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_padding() {
+        for key_len in [16, 24, 32] {
+            let mut aes = AesApplet {
+                mode: AesMode::Cbc,
+                key: vec![0x42; key_len],
+                iv: hex::decode(IV).unwrap(),
+                decrypt: false,
+                no_pad: false,
+            };
+            let enc = aes.process_test(b"YELLOW SUBMARINE".to_vec());
+            assert_eq!(enc.len(), 32);
+            aes.decrypt = true;
+            assert_eq!(aes.process_test(enc), b"YELLOW SUBMARINE");
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let explain = |args: &[&str]| {
+            build_applet(AesApplet::new().as_ref(), args)
+                .unwrap()
+                .explain()
+        };
+        assert_eq!(
+            explain(&["-m", "ecb", "-d", "-k", &"00".repeat(32)]),
+            "aes: decrypt with AES-256 in ECB mode, stripping PKCS#7 padding."
+        );
+        assert_eq!(
+            explain(&["-k", &"00".repeat(16), "--iv", &"01".repeat(16), "--no-pad"]),
+            format!(
+                "aes: encrypt with AES-128 in CBC mode with IV {}, without padding.",
+                "01".repeat(16)
+            )
+        );
+    }
+}
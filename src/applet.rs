@@ -1,6 +1,7 @@
 #![allow(clippy::new_ret_no_self)]
 use anyhow::{Context, Result};
 use clap::{arg, Command};
+use std::io::{Read, Write};
 
 pub trait Applet {
     /// The string which will define the subcommand.
@@ -15,6 +16,12 @@ pub trait Applet {
         true
     }
 
+    /// Overload to return "true" if the applet outputs text, which
+    /// can then be folded with the global `--fold` option.
+    fn returns_text(&self) -> bool {
+        false
+    }
+
     /// Receives the arguments as understood by `clap` and builds the resulting `Applet`.
     fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>>;
 
@@ -35,9 +42,31 @@ pub trait Applet {
         Some("value")
     }
 
+    /// Human-readable description of what the configured applet will do,
+    /// printed by the global `--explain` option. Applets with options should
+    /// overload it to describe them.
+    fn explain(&self) -> String {
+        format!("{}: {}", self.command(), self.description())
+    }
+
     /// Called by `main` to process the data in `val`
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>>;
 
+    /// Called by `main` for applets which return data. The default
+    /// implementation reads the whole input and calls `process`.
+    ///
+    /// Applets can overload this method to process huge inputs
+    /// incrementally, the output must be the same as `process`.
+    fn process_stream(&self, input: &mut dyn Read, output: &mut dyn Write) -> Result<()> {
+        let mut val = Vec::new();
+        input
+            .read_to_end(&mut val)
+            .with_context(|| "Reading input failed")?;
+        let res = self.process(val)?;
+        output.write_all(&res)?;
+        Ok(())
+    }
+
     /* No error wrapping to make it easier to test */
     #[cfg(test)]
     fn process_test(&self, val: Vec<u8>) -> Vec<u8> {
@@ -49,11 +78,57 @@ pub trait Applet {
         Self: Sized;
 }
 
+/// Build a configured applet from command line style arguments, for applets
+/// which use other applets internally.
+pub fn build_applet(app: &dyn Applet, args: &[&str]) -> Result<Box<dyn Applet>> {
+    let matches = app
+        .clap_command()
+        .try_get_matches_from(std::iter::once(app.command()).chain(args.iter().copied()))
+        .with_context(|| format!("Invalid arguments for {}: {:?}", app.command(), args))?;
+    app.parse_args(&matches)
+}
+
+/* Reader returning data in small chunks of varying sizes */
+#[cfg(test)]
+struct SlowReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+#[cfg(test)]
+impl Read for SlowReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = (self.pos % 7 + 1)
+            .min(buf.len())
+            .min(self.data.len() - self.pos);
+        buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/* Check that process_stream outputs the same as process, with the input
+ * read at once or in small chunks */
+#[cfg(test)]
+pub fn check_stream(app: &dyn Applet, data: &[u8]) {
+    let whole = app.process_test(data.to_vec());
+
+    let mut streamed = Vec::new();
+    app.process_stream(&mut &data[..], &mut streamed).unwrap();
+    assert_eq!(streamed, whole);
+
+    let mut streamed = Vec::new();
+    let mut reader = SlowReader { data, pos: 0 };
+    app.process_stream(&mut reader, &mut streamed).unwrap();
+    assert_eq!(streamed, whole);
+}
+
 /* Helper to trim whitespace
  * Note: trim_ascii does this in Rust 1.80
  * */
 pub trait SliceExt {
     fn trim(&self) -> &Self;
+    fn trim_bytes(&self, set: &[u8]) -> &Self;
 }
 
 impl SliceExt for [u8] {
@@ -72,6 +147,28 @@ impl SliceExt for [u8] {
             &[]
         }
     }
+
+    /* Trim leading and trailing bytes which are in the given set */
+    fn trim_bytes(&self, set: &[u8]) -> &[u8] {
+        let first = self
+            .iter()
+            .position(|c| !set.contains(c))
+            .unwrap_or(self.len());
+        let last = self
+            .iter()
+            .rposition(|c| !set.contains(c))
+            .map_or(first, |l| l + 1);
+        &self[first..last]
+    }
+}
+
+/* Parse a list of bytes given in hex, such as "22", "2227" or "22,5b 5d" */
+pub fn parse_byte_set(list: &str) -> Result<Vec<u8>> {
+    let cleaned: String = list
+        .chars()
+        .filter(|c| *c != ',' && !c.is_whitespace())
+        .collect();
+    hex::decode(cleaned).with_context(|| format!("Invalid hex byte list \"{}\"", list))
 }
 
 // We cannot use a default implementation for trait as from_str_radix is not defined
@@ -104,3 +201,45 @@ impl FromStrWithRadix for $x {
 }
 
 from_str_with_radix_for_types!(u64, i64, usize);
+
+/* Byte order of integer fields, shared by applets which parse or
+ * build binary structures */
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug, PartialEq)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// Read an unsigned integer from `bytes` (at most 8 bytes).
+    pub fn read_uint(&self, bytes: &[u8]) -> u64 {
+        let mut res: u64 = 0;
+        match self {
+            Endianness::Little => {
+                for b in bytes.iter().rev() {
+                    res = (res << 8) | (*b as u64);
+                }
+            }
+            Endianness::Big => {
+                for b in bytes.iter() {
+                    res = (res << 8) | (*b as u64);
+                }
+            }
+        }
+        res
+    }
+
+    /// Write `val` as a `size` bytes unsigned integer (at most 8 bytes).
+    /// Returns `None` if `val` does not fit.
+    pub fn write_uint(&self, val: u64, size: usize) -> Option<Vec<u8>> {
+        if size < 8 && val >> (size * 8) != 0 {
+            return None;
+        }
+        let le = val.to_le_bytes()[..size].to_vec();
+        Some(match self {
+            Endianness::Little => le,
+            Endianness::Big => le.into_iter().rev().collect(),
+        })
+    }
+}
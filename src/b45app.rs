@@ -0,0 +1,148 @@
+use crate::applet::Applet;
+use crate::applet::SliceExt;
+use anyhow::{bail, Context, Result};
+
+/* RFC 9285 alphabet */
+const B45_ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+pub struct B45EncApplet {}
+
+impl Applet for B45EncApplet {
+    fn command(&self) -> &'static str {
+        "b45"
+    }
+    fn description(&self) -> &'static str {
+        "base45 encode"
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {})
+    }
+
+    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {}))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let mut res = Vec::with_capacity(val.len() / 2 * 3 + 2);
+        for chunk in val.chunks(2) {
+            let (mut n, digits) = match chunk {
+                [a, b] => ((*a as usize) << 8 | *b as usize, 3),
+                _ => (chunk[0] as usize, 2),
+            };
+            for _ in 0..digits {
+                res.push(B45_ALPHABET[n % 45]);
+                n /= 45;
+            }
+        }
+        Ok(res)
+    }
+
+    fn returns_text(&self) -> bool {
+        true
+    }
+}
+
+pub struct B45DecApplet {}
+
+impl Applet for B45DecApplet {
+    fn command(&self) -> &'static str {
+        "unb45"
+    }
+    fn description(&self) -> &'static str {
+        "base45 decode"
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {})
+    }
+
+    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {}))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        // space is part of the alphabet, only trim line endings
+        let trimmed = val.trim_bytes(b"\r\n");
+        let digits = trimmed
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                B45_ALPHABET
+                    .iter()
+                    .position(|a| a == c)
+                    .with_context(|| format!("Invalid base45 character at offset {}", i))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        let mut res = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+        for chunk in digits.chunks(3) {
+            let n = chunk.iter().rev().fold(0, |acc, d| acc * 45 + d);
+            match chunk.len() {
+                3 if n <= 0xffff => res.extend_from_slice(&(n as u16).to_be_bytes()),
+                2 if n <= 0xff => res.push(n as u8),
+                1 => bail!("Invalid base45 input: length cannot be 3n+1"),
+                _ => bail!("Invalid base45 input: group value {} out of range", n),
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["b45", "Hello!!"])
+            .assert()
+            .stdout("%69 VD92EX0")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["unb45"])
+            .write_stdin("QED8WEX0\n")
+            .assert()
+            .stdout("ietf!")
+            .success();
+    }
+
+    #[test]
+    fn test_rfc_vectors() {
+        let enc = B45EncApplet {};
+        let dec = B45DecApplet {};
+        assert_eq!(enc.process_test(b"AB".to_vec()), b"BB8");
+        assert_eq!(enc.process_test(b"base-45".to_vec()), b"UJCLQE7W581");
+        assert_eq!(dec.process_test(b"QED8WEX0".to_vec()), b"ietf!");
+        assert_eq!(enc.process_test(vec![]), b"");
+        assert_eq!(dec.process_test(vec![]), b"");
+        assert_eq!(dec.process_test(b" 0\n".to_vec()), b"\x24");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let enc = B45EncApplet {};
+        let dec = B45DecApplet {};
+        let data: Vec<u8> = (0..=255).chain([0xff, 0xff, 0x00]).collect();
+        assert_eq!(dec.process_test(enc.process_test(data.clone())), data);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let dec = B45DecApplet {};
+        // 3n+1 length
+        assert!(dec.process(b"BB8A".to_vec()).is_err());
+        // ":::" = 91124 > 65535
+        assert!(dec.process(b":::".to_vec()).is_err());
+        // final group "::" = 2024 > 255
+        assert!(dec.process(b"BB8::".to_vec()).is_err());
+        // lowercase is not in the alphabet
+        assert!(dec.process(b"bb8".to_vec()).is_err());
+        // highest valid values
+        assert_eq!(dec.process_test(b"FGW".to_vec()), [0xff, 0xff]);
+        assert_eq!(dec.process_test(b"U5".to_vec()), [0xff]);
+    }
+}
@@ -5,6 +5,30 @@ use base64::engine::general_purpose;
 use base64::engine::Engine;
 use clap::{arg, Command};
 
+/* Describe the alphabet of an engine, recovered by encoding the 6-bit
+ * values 0 to 63 */
+fn alphabet_name(engine: &general_purpose::GeneralPurpose) -> String {
+    let sextets: Vec<u8> = (0..64).collect();
+    let bytes: Vec<u8> = sextets
+        .chunks(4)
+        .flat_map(|s| {
+            [
+                s[0] << 2 | s[1] >> 4,
+                s[1] << 4 | s[2] >> 2,
+                s[2] << 6 | s[3],
+            ]
+        })
+        .collect();
+    let chars = engine.encode(bytes);
+    if chars == base64::alphabet::STANDARD.as_str() {
+        "the standard alphabet".to_string()
+    } else if chars == base64::alphabet::URL_SAFE.as_str() {
+        "the URL-safe alphabet".to_string()
+    } else {
+        format!("the custom alphabet \"{}\"", chars)
+    }
+}
+
 pub struct B64EncApplet {
     engine: general_purpose::GeneralPurpose,
 }
@@ -52,9 +76,17 @@ impl Applet for B64EncApplet {
         }))
     }
 
+    fn explain(&self) -> String {
+        format!("b64: base64 encode, using {}.", alphabet_name(&self.engine))
+    }
+
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
         Ok(self.engine.encode(val).as_bytes().to_vec())
     }
+
+    fn returns_text(&self) -> bool {
+        true
+    }
 }
 
 pub struct B64DecApplet {
@@ -113,6 +145,18 @@ impl Applet for B64DecApplet {
         }))
     }
 
+    fn explain(&self) -> String {
+        format!(
+            "d64: base64 decode, using {}. {}",
+            alphabet_name(&self.engine),
+            if self.strict {
+                "Invalid data is an error."
+            } else {
+                "Invalid data is copied as is."
+            }
+        )
+    }
+
     /* b64_decode. With two modes:
      * - strict: decode until the end of the valid base64
      * - lenient: decode the b64 input until the first invalid byte
@@ -164,6 +208,7 @@ impl Applet for B64DecApplet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::applet::build_applet;
 
     #[test]
     fn test_b64_inv_lenient() {
@@ -173,7 +218,7 @@ mod tests {
 
         let d64 = B64DecApplet {
             strict: false,
-            engine: engine,
+            engine,
         };
         assert_eq!(
             "::::".as_bytes().to_vec(),
@@ -290,4 +335,21 @@ mod tests {
         let to_enc = [0x74, 0x65, 0x73, 0x74, 0x52, 0xaf, 0x20].to_vec();
         assert_eq!(to_enc, d64.process_test(b64.process_test(to_enc.clone())));
     }
+
+    #[test]
+    fn test_explain() {
+        let b64 = build_applet(B64EncApplet::new().as_ref(), &["-u"]).unwrap();
+        assert_eq!(
+            b64.explain(),
+            "b64: base64 encode, using the URL-safe alphabet."
+        );
+        let alphabet = "./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        let d64 = build_applet(B64DecApplet::new().as_ref(), &["-a", alphabet]).unwrap();
+        assert!(d64
+            .explain()
+            .contains(&format!("the custom alphabet \"{}\"", alphabet)));
+        assert!(B64DecApplet::new()
+            .explain()
+            .contains("the standard alphabet"));
+    }
 }
@@ -98,7 +98,7 @@ mod tests {
     fn test_base_cli_no_radix() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["base", "10"])
+            .args(["base", "10"])
             .assert()
             .stdout("0xa")
             .success();
@@ -108,7 +108,7 @@ mod tests {
     fn test_base_cli_arg() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["base", "0x10"])
+            .args(["base", "0x10"])
             .assert()
             .stdout("16")
             .success();
@@ -118,7 +118,7 @@ mod tests {
     fn test_base_cli_arg_from_to() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["base", "-f", "2", "-t", "16", "10000"])
+            .args(["base", "-f", "2", "-t", "16", "10000"])
             .assert()
             .stdout("10")
             .success();
@@ -128,7 +128,7 @@ mod tests {
     fn test_base_cli_stdin() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["base"])
+            .args(["base"])
             .write_stdin("0xA\n")
             .assert()
             .stdout("10")
@@ -139,7 +139,7 @@ mod tests {
     fn test_base_cli_arg_to() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["base", "-t", "32", "0o7675"])
+            .args(["base", "-t", "32", "0o7675"])
             .assert()
             .stdout("3tt")
             .success();
@@ -13,7 +13,7 @@ use regex::bytes::{Regex, RegexBuilder};
 /// Build the regex pattern with the given options.
 /// By default, the `unicode` flag is set to false, and `dot_matches_new_line` set to true.
 /// Code borrowed from gahag's bgrep <https://github.com/gahag/bgrep>
-fn build_pattern<P: AsRef<str>>(pattern: &P) -> Result<Regex> {
+pub fn build_pattern<P: AsRef<str>>(pattern: &P) -> Result<Regex> {
     let mut builder = RegexBuilder::new(pattern.as_ref());
 
     builder.unicode(false);
@@ -23,6 +23,22 @@ fn build_pattern<P: AsRef<str>>(pattern: &P) -> Result<Regex> {
         .with_context(|| "Could not build regular expression")
 }
 
+/// Convert hex pattern to "\x00" format
+pub fn hex_pattern(pattern_val: &str) -> Result<String> {
+    let mut s = String::new();
+    if pattern_val.len() % 2 != 0 {
+        bail!("hex pattern length is not even");
+    }
+    if !pattern_val.bytes().all(|c| c.is_ascii_hexdigit()) {
+        bail!("invalid char in hex pattern");
+    }
+    for i in 0..(pattern_val.len() / 2) {
+        s += "\\x";
+        s += &pattern_val[i * 2..i * 2 + 2];
+    }
+    Ok(s)
+}
+
 pub struct BgrepApplet {
     paths: Option<Vec<String>>,
     pattern: Option<Regex>,
@@ -74,23 +90,12 @@ impl Applet for BgrepApplet {
             .collect();
         let pattern_val = args.get_one::<String>("pattern").unwrap();
 
-        /* Convert hex pattern to "\x00" format if needed */
-        let mut s = String::new();
-        let final_pat = if args.get_flag("hex") {
-            if pattern_val.len() % 2 != 0 {
-                bail!("hex pattern length is not even");
-            }
-            for i in 0..(pattern_val.len() / 2) {
-                s += "\\x";
-                s += &pattern_val[i * 2..i * 2 + 2];
-            }
-            s.as_str()
+        let pattern = if args.get_flag("hex") {
+            build_pattern(&hex_pattern(pattern_val)?)?
         } else {
-            pattern_val
+            build_pattern(pattern_val)?
         };
 
-        let pattern = build_pattern(&final_pat)?;
-
         Ok(Box::new(Self {
             paths: Some(filenames),
             pattern: Some(pattern),
@@ -194,16 +199,16 @@ mod tests {
     #[test]
     fn test_cli() {
         let mut data: [u8; 10] = [0; 10];
-        for i in (0..10).into_iter() {
-            data[i] = i as u8;
+        for (i, d) in data.iter_mut().enumerate() {
+            *d = i as u8;
         }
 
         let mut tmpfile = tempfile::NamedTempFile::new().unwrap();
-        tmpfile.write(&data).unwrap();
+        tmpfile.write_all(&data).unwrap();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["bgrep", "-x", "020304", &tmpfile.path().to_str().unwrap()])
+            .args(["bgrep", "-x", "020304", tmpfile.path().to_str().unwrap()])
             .assert()
             .stdout("0x2\n")
             .success();
@@ -212,18 +217,18 @@ mod tests {
     #[test]
     fn test_cli_multiple() {
         let mut tmpfile1 = tempfile::NamedTempFile::new().unwrap();
-        tmpfile1.write(b"tmpfile1").unwrap();
+        tmpfile1.write_all(b"tmpfile1").unwrap();
 
         let mut tmpfile2 = tempfile::NamedTempFile::new().unwrap();
-        tmpfile2.write(b"2tmpfile").unwrap();
+        tmpfile2.write_all(b"2tmpfile").unwrap();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&[
+            .args([
                 "bgrep",
                 "tmpfile",
-                &tmpfile1.path().to_str().unwrap(),
-                &tmpfile2.path().to_str().unwrap(),
+                tmpfile1.path().to_str().unwrap(),
+                tmpfile2.path().to_str().unwrap(),
             ])
             .assert()
             .stdout(predicates::str::contains(": 0x0\n"))
@@ -236,13 +241,13 @@ mod tests {
         let tmp_dir = tempfile::TempDir::new().unwrap();
 
         {
-            let mut tmp_file = File::create(&tmp_dir.path().join("test_file.bin")).unwrap();
-            tmp_file.write(b"2tmpfile").unwrap();
+            let mut tmp_file = File::create(tmp_dir.path().join("test_file.bin")).unwrap();
+            tmp_file.write_all(b"2tmpfile").unwrap();
         }
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&[
+            .args([
                 "bgrep",
                 "--recursive",
                 "tmpfile",
@@ -0,0 +1,410 @@
+use crate::applet::{Applet, Endianness};
+use anyhow::{bail, Result};
+use clap::{arg, value_parser, Command};
+use std::convert::TryFrom;
+
+/* Position of needle in data, starting at from */
+fn find(data: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    data.get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| p + from)
+}
+
+/* Length of a PNG file: up to the end of the IEND chunk, with its CRC */
+fn png_len(data: &[u8]) -> Option<usize> {
+    find(data, 8, b"IEND\xae\x42\x60\x82").map(|p| p + 8)
+}
+
+/* Length of a JPEG file: walk the segments, scans end with the next marker
+ * which is neither a stuffed 0xff nor a restart marker, until EOI */
+fn jpeg_len(data: &[u8]) -> Option<usize> {
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xff {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        match marker {
+            0xd9 => return Some(pos + 2),
+            /* Fill bytes */
+            0xff => pos += 1,
+            0x01 | 0xd0..=0xd7 => pos += 2,
+            _ => {
+                let len = Endianness::Big.read_uint(data.get(pos + 2..pos + 4)?) as usize;
+                pos += 2 + len;
+                if marker == 0xda {
+                    while *data.get(pos)? != 0xff
+                        || matches!(*data.get(pos + 1)?, 0x00 | 0xd0..=0xd7)
+                    {
+                        pos += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn gif_len(data: &[u8]) -> Option<usize> {
+    find(data, 6, b"\x00\x3b").map(|p| p + 2)
+}
+
+/* Length of a ZIP file: up to the end of central directory record, with its comment */
+fn zip_len(data: &[u8]) -> Option<usize> {
+    let eocd = find(data, 4, b"PK\x05\x06")?;
+    let comment_len = Endianness::Little.read_uint(data.get(eocd + 20..eocd + 22)?) as usize;
+    Some(eocd + 22 + comment_len)
+}
+
+/* Length of a PDF file: up to the first %%EOF and its end of line. Incremental
+ * updates after it are not included */
+fn pdf_len(data: &[u8]) -> Option<usize> {
+    let eof = find(data, 5, b"%%EOF")? + 5;
+    let mut end = eof;
+    while end < data.len() && end - eof < 2 && matches!(data[end], b'\r' | b'\n') {
+        end += 1;
+    }
+    Some(end)
+}
+
+/* Length of an ELF file: up to the end of the section header table, which is
+ * usually last */
+fn elf_len(data: &[u8]) -> Option<usize> {
+    let endian = match data.get(5)? {
+        1 => Endianness::Little,
+        2 => Endianness::Big,
+        _ => return None,
+    };
+    let read = |off: usize, size: usize| Some(endian.read_uint(data.get(off..off + size)?));
+    let (shoff, shentsize, shnum) = match data.get(4)? {
+        1 => (read(0x20, 4)?, read(0x2e, 2)?, read(0x30, 2)?),
+        2 => (read(0x28, 8)?, read(0x3a, 2)?, read(0x3c, 2)?),
+        _ => return None,
+    };
+    if shoff == 0 {
+        return None;
+    }
+    usize::try_from(shoff.checked_add(shentsize * shnum)?).ok()
+}
+
+/* Length of a SQLite database: page size times page count */
+fn sqlite_len(data: &[u8]) -> Option<usize> {
+    let page_size = match Endianness::Big.read_uint(data.get(16..18)?) {
+        1 => 65536,
+        s => s,
+    };
+    let pages = Endianness::Big.read_uint(data.get(28..32)?);
+    usize::try_from(page_size.checked_mul(pages)?).ok()
+}
+
+/* Length of a 7z archive: signature header, then the next header */
+fn sevenzip_len(data: &[u8]) -> Option<usize> {
+    let offset = Endianness::Little.read_uint(data.get(12..20)?);
+    let size = Endianness::Little.read_uint(data.get(20..28)?);
+    usize::try_from(offset.checked_add(size)?.checked_add(32)?).ok()
+}
+
+/* Length of the file starting at the beginning of the data, if it can be determined */
+type LenFn = fn(&[u8]) -> Option<usize>;
+
+struct Signature {
+    name: &'static str,
+    magic: &'static [u8],
+    len: Option<LenFn>,
+}
+
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        name: "png",
+        magic: b"\x89PNG\r\n\x1a\n",
+        len: Some(png_len),
+    },
+    Signature {
+        name: "jpeg",
+        magic: b"\xff\xd8\xff",
+        len: Some(jpeg_len),
+    },
+    Signature {
+        name: "gif",
+        magic: b"GIF87a",
+        len: Some(gif_len),
+    },
+    Signature {
+        name: "gif",
+        magic: b"GIF89a",
+        len: Some(gif_len),
+    },
+    Signature {
+        name: "zip",
+        magic: b"PK\x03\x04",
+        len: Some(zip_len),
+    },
+    Signature {
+        name: "pdf",
+        magic: b"%PDF-",
+        len: Some(pdf_len),
+    },
+    Signature {
+        name: "elf",
+        magic: b"\x7fELF",
+        len: Some(elf_len),
+    },
+    Signature {
+        name: "sqlite",
+        magic: b"SQLite format 3\x00",
+        len: Some(sqlite_len),
+    },
+    Signature {
+        name: "7z",
+        magic: b"7z\xbc\xaf\x27\x1c",
+        len: Some(sevenzip_len),
+    },
+    Signature {
+        name: "gzip",
+        magic: b"\x1f\x8b\x08",
+        len: None,
+    },
+    Signature {
+        name: "xz",
+        magic: b"\xfd7zXZ\x00",
+        len: None,
+    },
+];
+
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    offset: usize,
+    name: &'static str,
+    len: usize,
+    /* Whether the length comes from the format, or is up to the next candidate */
+    exact: bool,
+}
+
+/* Candidates are sorted by offset. When the length of a file cannot be
+ * determined, it extends up to the next candidate, or the end of the input.
+ * Signatures inside a file of known length (zip entries, thumbnails, ...)
+ * are skipped */
+fn candidates(data: &[u8]) -> Vec<Candidate> {
+    let mut found: Vec<(usize, &Signature)> = vec![];
+    for offset in 0..data.len() {
+        for sig in SIGNATURES {
+            if data[offset..].starts_with(sig.magic) {
+                found.push((offset, sig));
+            }
+        }
+    }
+    let mut res: Vec<Candidate> = vec![];
+    let mut end = 0;
+    for (i, (offset, sig)) in found.iter().enumerate() {
+        if *offset < end {
+            continue;
+        }
+        let next = found.get(i + 1).map_or(data.len(), |n| n.0);
+        let len = sig.len.and_then(|f| f(&data[*offset..]));
+        let cand = Candidate {
+            offset: *offset,
+            name: sig.name,
+            len: len.map_or(next - offset, |l| l.min(data.len() - offset)),
+            exact: len.is_some(),
+        };
+        if cand.exact {
+            end = cand.offset + cand.len;
+        }
+        res.push(cand);
+    }
+    res
+}
+
+pub struct CarveApplet {
+    extract: Option<usize>,
+}
+
+impl Applet for CarveApplet {
+    fn command(&self) -> &'static str {
+        "carve"
+    }
+    fn description(&self) -> &'static str {
+        "find files embedded in data by their signatures"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-x --extract <N> "output the Nth file found, instead of listing them")
+                    .value_parser(value_parser!(u64)),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(format!(
+                "Outputs \"N: offset type (length)\" lines. Lengths prefixed with '~' are \
+                 unknown, up to the next file found.\nKnown types: {}.",
+                SIGNATURES
+                    .iter()
+                    .map(|s| s.name)
+                    .fold(Vec::<&str>::new(), |mut v, n| {
+                        if !v.contains(&n) {
+                            v.push(n);
+                        }
+                        v
+                    })
+                    .join(", ")
+            ))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { extract: None })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            extract: args.get_one::<u64>("extract").map(|n| *n as usize),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let found = candidates(&val);
+        if let Some(n) = self.extract {
+            return match found.get(n) {
+                Some(c) => Ok(val[c.offset..c.offset + c.len].to_vec()),
+                None => bail!("No file {}, {} found", n, found.len()),
+            };
+        }
+        let mut res = String::new();
+        for (i, c) in found.iter().enumerate() {
+            res.push_str(&format!(
+                "{}: 0x{:x} {} ({}{} bytes)\n",
+                i,
+                c.offset,
+                c.name,
+                if c.exact { "" } else { "~" },
+                c.len
+            ));
+        }
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* Minimal PNG: signature, IHDR and IEND chunks */
+    fn png() -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(b"\x00\x00\x00\x0dIHDR\x00\x00\x00\x01\x00\x00\x00\x01\x08\x00\x00\x00\x00\x3a\x7e\x9b\x55");
+        png.extend_from_slice(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+        png
+    }
+
+    #[test]
+    fn test_cli() {
+        let mut data = vec![0x41; 0x100];
+        data.extend(png());
+        data.extend([0x42; 0x20]);
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["carve"])
+            .write_stdin(data.clone())
+            .assert()
+            .stdout("0: 0x100 png (45 bytes)\n")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["carve", "--extract", "0"])
+            .write_stdin(data)
+            .assert()
+            .stdout(predicates::ord::eq(png()))
+            .success();
+    }
+
+    #[test]
+    fn test_candidates() {
+        let mut data = b"junk".to_vec();
+        data.extend(png());
+        data.extend(b"\x1f\x8b\x08\x00gzipdata");
+        data.extend(b"\xff\xd8\xff\xe0\x00\x04JF\xff\xda\x00\x02\x12\xff\x00\xff\xd0\x34\xff\xd9");
+        data.extend(b"trailer");
+        let found = candidates(&data);
+        assert_eq!(
+            found,
+            [
+                Candidate {
+                    offset: 4,
+                    name: "png",
+                    len: 45,
+                    exact: true
+                },
+                Candidate {
+                    offset: 49,
+                    name: "gzip",
+                    len: 12,
+                    exact: false
+                },
+                Candidate {
+                    offset: 61,
+                    name: "jpeg",
+                    len: 20,
+                    exact: true
+                },
+            ]
+        );
+
+        let carve = CarveApplet { extract: Some(2) };
+        assert!(carve.process_test(data.clone()).ends_with(b"\xff\xd9"));
+        let carve = CarveApplet { extract: Some(3) };
+        assert!(carve.process(data).is_err());
+
+        // truncated file
+        let png = png();
+        assert_eq!(candidates(&png[..20])[0].len, 20);
+        assert!(!candidates(&png[..20])[0].exact);
+
+        // zip with two entries, one of them a PNG: only the archive is reported
+        let mut zip = b"PK\x03\x04entry1".to_vec();
+        zip.extend(b"PK\x03\x04entry2");
+        zip.extend(&png);
+        zip.extend(b"PK\x01\x02cd1PK\x01\x02cd2");
+        zip.extend(
+            b"PK\x05\x06\x00\x00\x00\x00\x02\x00\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+        );
+        let mut data = zip.clone();
+        data.extend(b"\x1f\x8b\x08\x00gzipdata");
+        assert_eq!(
+            candidates(&data),
+            [
+                Candidate {
+                    offset: 0,
+                    name: "zip",
+                    len: zip.len(),
+                    exact: true
+                },
+                Candidate {
+                    offset: zip.len(),
+                    name: "gzip",
+                    len: 12,
+                    exact: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lengths() {
+        let zip = b"PK\x03\x04datadataPK\x05\x06\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x00hi";
+        assert_eq!(zip_len(zip), Some(zip.len()));
+        assert_eq!(pdf_len(b"%PDF-1.4 ... %%EOF\nnext"), Some(19));
+        assert_eq!(pdf_len(b"%PDF-1.4 ... %%EOF\r\n\r\n"), Some(20));
+        assert_eq!(gif_len(b"GIF89a\x01\x00\x00\x3bXX"), Some(10));
+
+        let mut elf = vec![0; 64];
+        elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        elf[0x28] = 0x40;
+        elf[0x3a] = 0x40;
+        elf[0x3c] = 2;
+        assert_eq!(elf_len(&elf), Some(0xc0));
+        elf[0x28] = 0;
+        assert_eq!(elf_len(&elf), None);
+    }
+}
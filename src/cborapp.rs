@@ -0,0 +1,581 @@
+use crate::applet::Applet;
+use crate::json::{escape_json_str, JsonFormat};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+use std::convert::TryFrom;
+
+/* Maximum nesting level, to avoid blowing the stack on malicious input */
+const MAX_DEPTH: usize = 256;
+
+/* Generic representation of decoded CBOR / MessagePack items */
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Int(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Ext(i8, Vec<u8>),
+    Float(f64),
+    Bool(bool),
+    Null,
+    Undefined,
+    Simple(u8),
+}
+
+impl Value {
+    /* Render as JSON-like text. Items which have no JSON
+     * equivalent use the CBOR diagnostic notation (RFC 8949 section 8) */
+    pub fn render(&self, fmt: &JsonFormat, level: usize, res: &mut String) {
+        match self {
+            Value::Int(i) => res.push_str(&i.to_string()),
+            Value::Bytes(b) => {
+                res.push_str("h'");
+                res.push_str(&hex::encode(b));
+                res.push('\'');
+            }
+            Value::Text(s) => escape_json_str(s, res),
+            Value::Array(a) => fmt.render_seq(('[', ']'), a, level, res, |v, res| {
+                v.render(fmt, level + 1, res)
+            }),
+            Value::Map(m) => fmt.render_seq(('{', '}'), m, level, res, |(k, v), res| {
+                k.render(fmt, level + 1, res);
+                res.push_str(fmt.key_sep());
+                v.render(fmt, level + 1, res);
+            }),
+            Value::Tag(t, v) => {
+                res.push_str(&format!("{}(", t));
+                v.render(fmt, level, res);
+                res.push(')');
+            }
+            Value::Ext(t, b) => res.push_str(&format!("ext({}, h'{}')", t, hex::encode(b))),
+            Value::Float(f) => {
+                if f.is_nan() {
+                    res.push_str("NaN");
+                } else if f.is_infinite() {
+                    res.push_str(if *f > 0.0 { "Infinity" } else { "-Infinity" });
+                } else {
+                    res.push_str(&format!("{:?}", f));
+                }
+            }
+            Value::Bool(b) => res.push_str(&b.to_string()),
+            Value::Null => res.push_str("null"),
+            Value::Undefined => res.push_str("undefined"),
+            Value::Simple(s) => res.push_str(&format!("simple({})", s)),
+        }
+    }
+}
+
+/* Convert IEEE 754 half-precision float */
+fn f16_to_f64(h: u16) -> f64 {
+    let exp = (h >> 10) & 0x1f;
+    let mant = (h & 0x3ff) as f64;
+    let val = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 => {
+            if mant == 0.0 {
+                f64::INFINITY
+            } else {
+                f64::NAN
+            }
+        }
+        _ => (mant + 1024.0) * 2f64.powi(exp as i32 - 25),
+    };
+    if h & 0x8000 != 0 {
+        -val
+    } else {
+        val
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() - self.pos < len {
+            bail!(
+                "Truncated input: need {} bytes at offset 0x{:x}",
+                len,
+                self.pos
+            );
+        }
+        let res = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(res)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self, size: usize) -> Result<u64> {
+        Ok(self
+            .take(size)?
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | (*b as u64)))
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            bail!("Maximum nesting depth exceeded at offset 0x{:x}", self.pos);
+        }
+        Ok(())
+    }
+
+    /* Read the "argument" of a CBOR item, None means indefinite length */
+    fn cbor_arg(&mut self, info: u8) -> Result<Option<u64>> {
+        match info {
+            0..=23 => Ok(Some(info as u64)),
+            24 => Ok(Some(self.uint(1)?)),
+            25 => Ok(Some(self.uint(2)?)),
+            26 => Ok(Some(self.uint(4)?)),
+            27 => Ok(Some(self.uint(8)?)),
+            31 => Ok(None),
+            _ => bail!(
+                "Invalid additional info {} at offset 0x{:x}",
+                info,
+                self.pos - 1
+            ),
+        }
+    }
+
+    fn cbor_len(&mut self, info: u8) -> Result<usize> {
+        match self.cbor_arg(info)? {
+            Some(l) => usize::try_from(l).context("Length too big"),
+            None => bail!("Unexpected indefinite length at offset 0x{:x}", self.pos),
+        }
+    }
+
+    fn is_break(&mut self) -> Result<bool> {
+        if self.data.get(self.pos) == Some(&0xff) {
+            self.pos += 1;
+            Ok(true)
+        } else if self.at_end() {
+            bail!("Truncated input: missing break in indefinite length item")
+        } else {
+            Ok(false)
+        }
+    }
+
+    /* Indefinite length strings are a sequence of definite length chunks
+     * of the same major type */
+    fn cbor_chunks(&mut self, major: u8, info: u8) -> Result<Vec<u8>> {
+        if info != 31 {
+            let len = self.cbor_len(info)?;
+            return Ok(self.take(len)?.to_vec());
+        }
+        let mut res = Vec::new();
+        while !self.is_break()? {
+            let ib = self.byte()?;
+            if ib >> 5 != major || ib & 0x1f == 31 {
+                bail!(
+                    "Invalid chunk in indefinite string at offset 0x{:x}",
+                    self.pos - 1
+                );
+            }
+            let len = self.cbor_len(ib & 0x1f)?;
+            res.extend_from_slice(self.take(len)?);
+        }
+        Ok(res)
+    }
+
+    fn cbor_item(&mut self, depth: usize) -> Result<Value> {
+        self.check_depth(depth)?;
+        let ib = self.byte()?;
+        let (major, info) = (ib >> 5, ib & 0x1f);
+        match major {
+            0 => Ok(Value::Int(self.cbor_len_u64(info)? as i128)),
+            1 => Ok(Value::Int(-1 - self.cbor_len_u64(info)? as i128)),
+            2 => Ok(Value::Bytes(self.cbor_chunks(major, info)?)),
+            3 => {
+                let s = self.cbor_chunks(major, info)?;
+                Ok(Value::Text(String::from_utf8_lossy(&s).into_owned()))
+            }
+            4 => {
+                let mut res = Vec::new();
+                match self.cbor_arg(info)? {
+                    Some(len) => {
+                        for _ in 0..len {
+                            res.push(self.cbor_item(depth + 1)?);
+                        }
+                    }
+                    None => {
+                        while !self.is_break()? {
+                            res.push(self.cbor_item(depth + 1)?);
+                        }
+                    }
+                }
+                Ok(Value::Array(res))
+            }
+            5 => {
+                let mut res = Vec::new();
+                match self.cbor_arg(info)? {
+                    Some(len) => {
+                        for _ in 0..len {
+                            let k = self.cbor_item(depth + 1)?;
+                            res.push((k, self.cbor_item(depth + 1)?));
+                        }
+                    }
+                    None => {
+                        while !self.is_break()? {
+                            let k = self.cbor_item(depth + 1)?;
+                            res.push((k, self.cbor_item(depth + 1)?));
+                        }
+                    }
+                }
+                Ok(Value::Map(res))
+            }
+            6 => {
+                let tag = self.cbor_len_u64(info)?;
+                Ok(Value::Tag(tag, Box::new(self.cbor_item(depth + 1)?)))
+            }
+            _ => match info {
+                20 => Ok(Value::Bool(false)),
+                21 => Ok(Value::Bool(true)),
+                22 => Ok(Value::Null),
+                23 => Ok(Value::Undefined),
+                24 => Ok(Value::Simple(self.byte()?)),
+                25 => Ok(Value::Float(f16_to_f64(self.uint(2)? as u16))),
+                26 => Ok(Value::Float(f32::from_bits(self.uint(4)? as u32) as f64)),
+                27 => Ok(Value::Float(f64::from_bits(self.uint(8)?))),
+                31 => bail!("Unexpected break at offset 0x{:x}", self.pos - 1),
+                0..=19 => Ok(Value::Simple(info)),
+                _ => bail!(
+                    "Invalid simple value {} at offset 0x{:x}",
+                    info,
+                    self.pos - 1
+                ),
+            },
+        }
+    }
+
+    fn cbor_len_u64(&mut self, info: u8) -> Result<u64> {
+        match self.cbor_arg(info)? {
+            Some(v) => Ok(v),
+            None => bail!("Unexpected indefinite length at offset 0x{:x}", self.pos),
+        }
+    }
+
+    fn msgpack_array(&mut self, len: u64, depth: usize) -> Result<Value> {
+        let mut res = Vec::new();
+        for _ in 0..len {
+            res.push(self.msgpack_item(depth + 1)?);
+        }
+        Ok(Value::Array(res))
+    }
+
+    fn msgpack_map(&mut self, len: u64, depth: usize) -> Result<Value> {
+        let mut res = Vec::new();
+        for _ in 0..len {
+            let k = self.msgpack_item(depth + 1)?;
+            res.push((k, self.msgpack_item(depth + 1)?));
+        }
+        Ok(Value::Map(res))
+    }
+
+    fn msgpack_str(&mut self, len: usize) -> Result<Value> {
+        Ok(Value::Text(
+            String::from_utf8_lossy(self.take(len)?).into_owned(),
+        ))
+    }
+
+    fn msgpack_ext(&mut self, len: usize) -> Result<Value> {
+        let t = self.byte()? as i8;
+        Ok(Value::Ext(t, self.take(len)?.to_vec()))
+    }
+
+    fn msgpack_item(&mut self, depth: usize) -> Result<Value> {
+        self.check_depth(depth)?;
+        let b = self.byte()?;
+        match b {
+            0x00..=0x7f => Ok(Value::Int(b as i128)),
+            0x80..=0x8f => self.msgpack_map((b & 0xf) as u64, depth),
+            0x90..=0x9f => self.msgpack_array((b & 0xf) as u64, depth),
+            0xa0..=0xbf => self.msgpack_str((b & 0x1f) as usize),
+            0xc0 => Ok(Value::Null),
+            0xc2 => Ok(Value::Bool(false)),
+            0xc3 => Ok(Value::Bool(true)),
+            0xc4..=0xc6 => {
+                let len = self.uint(1 << (b - 0xc4))? as usize;
+                Ok(Value::Bytes(self.take(len)?.to_vec()))
+            }
+            0xc7..=0xc9 => {
+                let len = self.uint(1 << (b - 0xc7))? as usize;
+                self.msgpack_ext(len)
+            }
+            0xca => Ok(Value::Float(f32::from_bits(self.uint(4)? as u32) as f64)),
+            0xcb => Ok(Value::Float(f64::from_bits(self.uint(8)?))),
+            0xcc..=0xcf => Ok(Value::Int(self.uint(1 << (b - 0xcc))? as i128)),
+            0xd0..=0xd3 => {
+                let size = 1 << (b - 0xd0);
+                let v = self.uint(size)?;
+                // sign extend
+                let shift = 64 - size * 8;
+                Ok(Value::Int((((v << shift) as i64) >> shift) as i128))
+            }
+            0xd4..=0xd8 => self.msgpack_ext(1 << (b - 0xd4)),
+            0xd9..=0xdb => {
+                let len = self.uint(1 << (b - 0xd9))? as usize;
+                self.msgpack_str(len)
+            }
+            0xdc | 0xdd => {
+                let len = self.uint(2 << (b - 0xdc))?;
+                self.msgpack_array(len, depth)
+            }
+            0xde | 0xdf => {
+                let len = self.uint(2 << (b - 0xde))?;
+                self.msgpack_map(len, depth)
+            }
+            0xe0..=0xff => Ok(Value::Int((b as i8) as i128)),
+            0xc1 => bail!(
+                "Invalid MessagePack byte 0xc1 at offset 0x{:x}",
+                self.pos - 1
+            ),
+        }
+    }
+}
+
+fn cbor_head(major: u8, val: u64, res: &mut Vec<u8>) {
+    let m = major << 5;
+    if val < 24 {
+        res.push(m | val as u8);
+    } else if val <= 0xff {
+        res.push(m | 24);
+        res.push(val as u8);
+    } else if val <= 0xffff {
+        res.push(m | 25);
+        res.extend_from_slice(&(val as u16).to_be_bytes());
+    } else if val <= 0xffff_ffff {
+        res.push(m | 26);
+        res.extend_from_slice(&(val as u32).to_be_bytes());
+    } else {
+        res.push(m | 27);
+        res.extend_from_slice(&val.to_be_bytes());
+    }
+}
+
+fn json_to_cbor(val: &serde_json::Value, res: &mut Vec<u8>) {
+    match val {
+        serde_json::Value::Null => res.push(0xf6),
+        serde_json::Value::Bool(b) => res.push(if *b { 0xf5 } else { 0xf4 }),
+        serde_json::Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                cbor_head(0, u, res);
+            } else if let Some(i) = n.as_i64() {
+                cbor_head(1, (-1 - i) as u64, res);
+            } else {
+                res.push(0xfb);
+                res.extend_from_slice(&n.as_f64().unwrap_or(f64::NAN).to_be_bytes());
+            }
+        }
+        serde_json::Value::String(s) => {
+            cbor_head(3, s.len() as u64, res);
+            res.extend_from_slice(s.as_bytes());
+        }
+        serde_json::Value::Array(a) => {
+            cbor_head(4, a.len() as u64, res);
+            for v in a {
+                json_to_cbor(v, res);
+            }
+        }
+        serde_json::Value::Object(o) => {
+            cbor_head(5, o.len() as u64, res);
+            for (k, v) in o {
+                cbor_head(3, k.len() as u64, res);
+                res.extend_from_slice(k.as_bytes());
+                json_to_cbor(v, res);
+            }
+        }
+    }
+}
+
+pub struct CborApplet {
+    msgpack: bool,
+    reverse: bool,
+    format: JsonFormat,
+}
+
+impl CborApplet {
+    /* Decode all the items in the input (CBOR sequence or MessagePack stream) */
+    fn decode(&self, val: &[u8]) -> Result<Vec<Value>> {
+        let mut dec = Decoder::new(val);
+        let mut res = Vec::new();
+        while !dec.at_end() {
+            res.push(if self.msgpack {
+                dec.msgpack_item(0)?
+            } else {
+                dec.cbor_item(0)?
+            });
+        }
+        Ok(res)
+    }
+}
+
+impl Applet for CborApplet {
+    fn command(&self) -> &'static str {
+        "cbor"
+    }
+    fn description(&self) -> &'static str {
+        "decode CBOR or MessagePack data"
+    }
+
+    fn clap_command(&self) -> Command {
+        JsonFormat::add_args(Command::new(self.command()))
+            .about(self.description())
+            .arg(arg!(-m --msgpack "input is MessagePack"))
+            .arg(arg!(-r --reverse "encode JSON input to CBOR").conflicts_with("msgpack"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Items without a JSON equivalent are shown using the CBOR diagnostic notation.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            msgpack: false,
+            reverse: false,
+            format: JsonFormat::default(),
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            msgpack: args.get_flag("msgpack"),
+            reverse: args.get_flag("reverse"),
+            format: JsonFormat::from_args(args),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.reverse {
+            let json: serde_json::Value =
+                serde_json::from_slice(&val).context("Could not parse JSON input")?;
+            let mut res = Vec::new();
+            json_to_cbor(&json, &mut res);
+            return Ok(res);
+        }
+        let mut res = String::new();
+        for item in self.decode(&val)? {
+            item.render(&self.format, 0, &mut res);
+            res.push('\n');
+        }
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_reverse() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["cbor", "-r", r#"{"a": 1, "b": [-2, "c"]}"#])
+            .assert()
+            .stdout(&b"\xa2\x61a\x01\x61b\x82\x21\x61c"[..])
+            .success();
+    }
+
+    #[test]
+    fn test_cli_compact() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["cbor", "--compact"])
+            .write_stdin(&b"\xa2\x61a\x01\x61b\x82\x21\x61c"[..])
+            .assert()
+            .stdout("{\"a\":1,\"b\":[-2,\"c\"]}\n")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["cbor", "--indent", "4"])
+            .write_stdin(&b"\xa1\x61a\x81\x01"[..])
+            .assert()
+            .stdout("{\n    \"a\": [\n        1\n    ]\n}\n")
+            .success();
+    }
+
+    #[test]
+    fn test_cbor_map() {
+        let cbor = CborApplet {
+            msgpack: false,
+            reverse: false,
+            format: JsonFormat::default(),
+        };
+        assert_eq!(
+            String::from_utf8(
+                cbor.process_test(b"\xa2\x61a\x01\x61b\x82\x02\x43\x00\x01\x02".to_vec())
+            )
+            .unwrap(),
+            "{\n  \"a\": 1,\n  \"b\": [\n    2,\n    h'000102'\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_cbor_indefinite() {
+        let cbor = CborApplet {
+            msgpack: false,
+            reverse: false,
+            format: JsonFormat::default(),
+        };
+        // [_ "ab" "c", {_ 1: -1.5}] with an indefinite string
+        assert_eq!(
+            String::from_utf8(
+                cbor.process_test(b"\x9f\x7f\x62ab\x61c\xff\xbf\x01\xf9\xbe\x00\xff\xff".to_vec())
+            )
+            .unwrap(),
+            "[\n  \"abc\",\n  {\n    1: -1.5\n  }\n]\n"
+        );
+        // missing break
+        assert!(cbor.process(b"\x9f\x01\x02".to_vec()).is_err());
+        // truncated
+        assert!(cbor.process(b"\x62a".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_msgpack() {
+        let cbor = CborApplet {
+            msgpack: true,
+            reverse: false,
+            format: JsonFormat::default(),
+        };
+        // {"a": -1, "b": [true, nil]}
+        assert_eq!(
+            String::from_utf8(cbor.process_test(b"\x82\xa1a\xff\xa1b\x92\xc3\xc0".to_vec()))
+                .unwrap(),
+            "{\n  \"a\": -1,\n  \"b\": [\n    true,\n    null\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let enc = CborApplet {
+            msgpack: false,
+            reverse: true,
+            format: JsonFormat::default(),
+        };
+        let dec = CborApplet {
+            msgpack: false,
+            reverse: false,
+            format: JsonFormat::default(),
+        };
+        let json = b"{\"k\": [1, 1000, -100000, 1.5, null, false, \"t\\\"\"]}".to_vec();
+        assert_eq!(
+            String::from_utf8(dec.process_test(enc.process_test(json))).unwrap(),
+            "{\n  \"k\": [\n    1,\n    1000,\n    -100000,\n    1.5,\n    null,\n    false,\n    \"t\\\"\"\n  ]\n}\n"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use crate::applet::{Applet, SliceExt};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug)]
+enum ChecksumMode {
+    #[default]
+    Xor,
+    Sum,
+    Sum8,
+    Adler32,
+    Fletcher16,
+    Fletcher32,
+}
+
+impl ChecksumMode {
+    /* Compute the checksum, returned as big endian bytes */
+    fn compute(&self, val: &[u8]) -> Vec<u8> {
+        match self {
+            ChecksumMode::Xor => vec![val.iter().fold(0u8, |acc, b| acc ^ b)],
+            ChecksumMode::Sum => val
+                .iter()
+                .fold(0u16, |acc, b| acc.wrapping_add(*b as u16))
+                .to_be_bytes()
+                .to_vec(),
+            ChecksumMode::Sum8 => vec![val.iter().fold(0u8, |acc, b| acc.wrapping_add(*b))],
+            ChecksumMode::Adler32 => {
+                let (a, b) = val.iter().fold((1u32, 0u32), |(a, b), c| {
+                    let a = (a + *c as u32) % 65521;
+                    (a, (b + a) % 65521)
+                });
+                ((b << 16) | a).to_be_bytes().to_vec()
+            }
+            ChecksumMode::Fletcher16 => {
+                let (a, b) = val.iter().fold((0u16, 0u16), |(a, b), c| {
+                    let a = (a + *c as u16) % 255;
+                    (a, (b + a) % 255)
+                });
+                ((b << 8) | a).to_be_bytes().to_vec()
+            }
+            ChecksumMode::Fletcher32 => {
+                // 16-bit little endian words, odd length input is padded with 0
+                let (a, b) = val.chunks(2).fold((0u32, 0u32), |(a, b), w| {
+                    let word = w[0] as u32 | (*w.get(1).unwrap_or(&0) as u32) << 8;
+                    let a = (a + word) % 65535;
+                    (a, (b + a) % 65535)
+                });
+                ((b << 16) | a).to_be_bytes().to_vec()
+            }
+        }
+    }
+}
+
+pub struct ChecksumApplet {
+    mode: ChecksumMode,
+    verify: bool,
+    nmea: bool,
+    print: bool,
+}
+
+impl ChecksumApplet {
+    /* NMEA 0183: XOR of all chars between '$' and '*', as 2 hex digits */
+    fn process_nmea(&self, val: &[u8]) -> Result<Vec<u8>> {
+        let sentence = val.trim();
+        let (body, expected) = if self.verify {
+            let star = sentence
+                .iter()
+                .rposition(|c| *c == b'*')
+                .context("NMEA sentence has no '*' checksum delimiter")?;
+            (&sentence[..star], Some(&sentence[star + 1..]))
+        } else {
+            (sentence, None)
+        };
+        let data = body.strip_prefix(b"$").unwrap_or(body);
+        let sum = format!("{:02X}", ChecksumMode::Xor.compute(data)[0]);
+        if let Some(expected) = expected {
+            if !expected.eq_ignore_ascii_case(sum.as_bytes()) {
+                bail!(
+                    "Checksum mismatch: expected {}, computed {}",
+                    String::from_utf8_lossy(expected),
+                    sum
+                );
+            }
+            Ok(sentence.to_vec())
+        } else {
+            let mut res = body.to_vec();
+            res.push(b'*');
+            res.extend_from_slice(sum.as_bytes());
+            Ok(res)
+        }
+    }
+}
+
+impl Applet for ChecksumApplet {
+    fn command(&self) -> &'static str {
+        "checksum"
+    }
+    fn description(&self) -> &'static str {
+        "append, verify or print a simple checksum"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-m --mode <mode> "checksum algorithm")
+                    .value_parser(clap::builder::EnumValueParser::<ChecksumMode>::new())
+                    .default_value("xor"),
+            )
+            .arg(arg!(-c --verify "verify the checksum at the end of the input, and strip it"))
+            .arg(
+                arg!(-n --nmea "NMEA 0183 sentence, with ASCII '*HH' checksum")
+                    .conflicts_with("mode"),
+            )
+            .arg(
+                arg!(-p --print "only output the checksum, in hex")
+                    .conflicts_with_all(["verify", "nmea"]),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Modes:\n  \
+                 xor:  XOR of all bytes (1 byte)\n  \
+                 sum:  16-bit sum of all bytes (2 bytes, big endian)\n  \
+                 sum8: 8-bit sum of all bytes (1 byte)\n  \
+                 adler32: Adler-32, as used by zlib (4 bytes)\n  \
+                 fletcher16: Fletcher-16 (2 bytes)\n  \
+                 fletcher32: Fletcher-32, over 16-bit little endian words (4 bytes)\n\n\
+                 Multi-bytes checksums are appended in big endian order.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            mode: ChecksumMode::Xor,
+            verify: false,
+            nmea: false,
+            print: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            mode: *args.get_one::<ChecksumMode>("mode").unwrap(),
+            verify: args.get_flag("verify"),
+            nmea: args.get_flag("nmea"),
+            print: args.get_flag("print"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.nmea {
+            return self.process_nmea(&val);
+        }
+        if self.print {
+            return Ok(hex::encode(self.mode.compute(&val)).into_bytes());
+        }
+        if self.verify {
+            let sum_len = self.mode.compute(&[]).len();
+            if val.len() < sum_len {
+                bail!("Input is shorter than the checksum");
+            }
+            let (data, expected) = val.split_at(val.len() - sum_len);
+            let sum = self.mode.compute(data);
+            if sum != expected {
+                bail!(
+                    "Checksum mismatch: expected {}, computed {}",
+                    hex::encode(expected),
+                    hex::encode(sum)
+                );
+            }
+            Ok(data.to_vec())
+        } else {
+            let mut res = val;
+            let mut sum = self.mode.compute(&res);
+            res.append(&mut sum);
+            Ok(res)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_nmea() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["checksum", "--nmea"])
+            .write_stdin("$GPGLL,5057.970,N,00146.110,E,142451,A\n")
+            .assert()
+            .stdout("$GPGLL,5057.970,N,00146.110,E,142451,A*27")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["checksum", "--nmea", "-c"])
+            .write_stdin("$GPGLL,5057.970,N,00146.110,E,142451,A*28\n")
+            .assert()
+            .stderr(predicates::str::contains("expected 28, computed 27"))
+            .failure();
+    }
+
+    #[test]
+    fn test_xor() {
+        let cs = ChecksumApplet {
+            mode: ChecksumMode::Xor,
+            verify: false,
+            nmea: false,
+            print: false,
+        };
+        assert_eq!(cs.process_test(b"hello world".to_vec()), b"hello world\x20");
+        assert_eq!(cs.process_test(vec![]), vec![0]);
+
+        let verif = ChecksumApplet {
+            mode: ChecksumMode::Xor,
+            verify: true,
+            nmea: false,
+            print: false,
+        };
+        assert_eq!(
+            verif.process_test(b"hello world\x20".to_vec()),
+            b"hello world"
+        );
+        assert!(verif.process(b"hello world\x21".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_sum() {
+        let cs = ChecksumApplet {
+            mode: ChecksumMode::Sum,
+            verify: false,
+            nmea: false,
+            print: false,
+        };
+        assert_eq!(
+            cs.process_test(b"hello world".to_vec()),
+            b"hello world\x04\x5c"
+        );
+        // 258 * 0xff wraps around
+        assert_eq!(cs.process_test(vec![0xff; 258])[258..], [0x00, 0xfe]);
+
+        let cs8 = ChecksumApplet {
+            mode: ChecksumMode::Sum8,
+            verify: false,
+            nmea: false,
+            print: false,
+        };
+        assert_eq!(
+            cs8.process_test(b"hello world".to_vec()),
+            b"hello world\x5c"
+        );
+
+        let verif = ChecksumApplet {
+            mode: ChecksumMode::Sum,
+            verify: true,
+            nmea: false,
+            print: false,
+        };
+        assert_eq!(
+            verif.process_test(b"hello world\x04\x5c".to_vec()),
+            b"hello world"
+        );
+        assert!(verif.process(b"\x04".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_cli_adler32() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["checksum", "-m", "adler32", "-p", "Wikipedia"])
+            .assert()
+            .stdout("11e60398")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["checksum", "-m", "adler32", "-c"])
+            .write_stdin(&b"Wikipedia\x11\xe6\x03\x98"[..])
+            .assert()
+            .stdout("Wikipedia")
+            .success();
+    }
+
+    #[test]
+    fn test_adler_fletcher() {
+        assert_eq!(ChecksumMode::Adler32.compute(b""), [0, 0, 0, 1]);
+        assert_eq!(
+            ChecksumMode::Adler32.compute(b"Wikipedia"),
+            [0x11, 0xe6, 0x03, 0x98]
+        );
+        assert_eq!(ChecksumMode::Fletcher16.compute(b"abcde"), [0xc8, 0xf0]);
+        assert_eq!(ChecksumMode::Fletcher16.compute(b"abcdef"), [0x20, 0x57]);
+        assert_eq!(
+            ChecksumMode::Fletcher32.compute(b"abcde"),
+            [0xf0, 0x4f, 0xc7, 0x29]
+        );
+        assert_eq!(
+            ChecksumMode::Fletcher32.compute(b"abcdef"),
+            [0x56, 0x50, 0x2d, 0x2a]
+        );
+        // Fletcher-16 sums do not overflow with large inputs
+        assert_eq!(ChecksumMode::Fletcher16.compute(&[0xff; 1000]), [0, 0]);
+    }
+}
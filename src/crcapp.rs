@@ -1,7 +1,7 @@
 extern crate crc;
-use crate::applet::Applet;
-use anyhow::{bail, Result};
-use clap::{arg, Command};
+use crate::applet::{Applet, FromStrWithRadix};
+use anyhow::{bail, Context, Result};
+use clap::{arg, value_parser, Command};
 use crc::*;
 use std::process;
 
@@ -167,8 +167,55 @@ impl Applet for CRC32Applet {
     }
 }
 
+/* CRC parameters, using the Rocksoft model (as CRC RevEng does):
+ * init is the initial register value, refin reflects each input byte,
+ * refout reflects the final register, before xorout is applied */
+pub struct CrcModel {
+    width: u8,
+    poly: u64,
+    init: u64,
+    refin: bool,
+    refout: bool,
+    xorout: u64,
+}
+
+impl CrcModel {
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
+    }
+
+    /* Bitwise implementation: slow, but works for any width */
+    pub fn checksum(&self, val: &[u8]) -> u64 {
+        let mask = self.mask();
+        let top = 1u64 << (self.width - 1);
+        let mut crc = self.init;
+        for b in val {
+            let b = if self.refin { b.reverse_bits() } else { *b };
+            for i in (0..8).rev() {
+                let bit = (b >> i) & 1 == 1;
+                let msb = crc & top != 0;
+                crc = (crc << 1) & mask;
+                if bit != msb {
+                    crc ^= self.poly;
+                }
+            }
+        }
+        if self.refout {
+            crc = crc.reverse_bits() >> (64 - self.width);
+        }
+        (crc ^ self.xorout) & mask
+    }
+
+    pub fn format(&self, crc: u64) -> String {
+        format!("{:0width$x}", crc, width = (self.width as usize + 3) / 4)
+    }
+}
+
 pub struct CRCApplet {
     crctype: String,
+    custom: Option<CrcModel>,
+    identify: Option<u128>,
+    per_line: bool,
 }
 
 macro_rules! algs {
@@ -191,6 +238,9 @@ impl Applet for CRCApplet {
     fn new() -> Box<dyn Applet> {
         Box::new(Self {
             crctype: "lol".to_string(),
+            custom: None,
+            identify: None,
+            per_line: false,
         })
     }
 
@@ -198,11 +248,49 @@ impl Applet for CRCApplet {
         Command::new(self.command())
             .about(self.description())
             .arg(arg!(-l --list  "List supported CRC algorithms"))
+            .arg(
+                arg!(--custom "custom CRC, defined by the parameters below")
+                    .requires_all(["width", "poly"])
+                    .conflicts_with("list"),
+            )
+            .arg(
+                arg!(--width <N> "custom CRC: width in bits")
+                    .value_parser(value_parser!(u8).range(1..=64))
+                    .requires("custom"),
+            )
+            .arg(arg!(--poly <POLY> "custom CRC: polynomial, without the top bit").requires("custom"))
+            .arg(arg!(--init <INIT> "custom CRC: initial register value").requires("custom"))
+            .arg(arg!(--refin "custom CRC: reflect input bytes").requires("custom"))
+            .arg(arg!(--refout "custom CRC: reflect output before xorout").requires("custom"))
+            .arg(arg!(--xorout <XOROUT> "custom CRC: value to xor with the final register").requires("custom"))
+            .arg(
+                arg!(--identify "find which known algorithms produce the --expected CRC")
+                    .requires("expected")
+                    .conflicts_with_all(["list", "custom"]),
+            )
+            .arg(arg!(--expected <HEX> "expected CRC value, in hex").requires("identify"))
+            .arg(
+                arg!(--"per-line" "compute the CRC of each line, output \"offset crc\" lines")
+                    .conflicts_with_all(["list", "identify"]),
+            )
             .arg(
                 arg!([type] "CRC type to compute. Use 'all' to compute all known algorithms.")
-                    .required_unless_present("list"),
+                    .required_unless_present_any(["list", "custom", "identify"]),
             )
             .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "With --custom or --identify, no type is given and the first argument is the input value.\n\
+                 Example (CRC-8/MAXIM): crc --custom --width 8 --poly 0x31 --refin --refout 123456789\n\
+                 With --per-line, lines exclude their '\\n', a final newline does not start a new line.",
+            )
+    }
+
+    fn arg_or_stdin(&self) -> Option<&'static str> {
+        if self.custom.is_some() || self.identify.is_some() {
+            Some("type")
+        } else {
+            Some("value")
+        }
     }
 
     fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
@@ -212,12 +300,93 @@ impl Applet for CRCApplet {
             println!("\nSee https://docs.rs/crc/ for more info");
             process::exit(0);
         }
+        if (args.get_flag("custom") || args.get_flag("identify")) && args.contains_id("value") {
+            bail!("Too many arguments: no CRC type is expected with --custom or --identify");
+        }
+        if args.get_flag("identify") {
+            let expected = args.get_one::<String>("expected").unwrap();
+            let hex = expected.trim().trim_start_matches("0x").replace(' ', "");
+            let expected = u128::from_str_radix(&hex, 16)
+                .with_context(|| format!("Invalid expected CRC \"{}\"", expected))?;
+            return Ok(Box::new(Self {
+                crctype: "identify".to_string(),
+                custom: None,
+                identify: Some(expected),
+                per_line: false,
+            }));
+        }
+        if args.get_flag("custom") {
+            let width = *args.get_one::<u8>("width").unwrap();
+            let param = |name: &str| -> Result<u64> {
+                let val = match args.get_one::<String>(name) {
+                    Some(v) => u64::from_str_with_radix(v)
+                        .with_context(|| format!("Invalid --{} value", name))?,
+                    None => 0,
+                };
+                if width < 64 && val >> width != 0 {
+                    bail!(
+                        "--{} value 0x{:x} does not fit in {} bits",
+                        name,
+                        val,
+                        width
+                    );
+                }
+                Ok(val)
+            };
+            let model = CrcModel {
+                width,
+                poly: param("poly")?,
+                init: param("init")?,
+                refin: args.get_flag("refin"),
+                refout: args.get_flag("refout"),
+                xorout: param("xorout")?,
+            };
+            return Ok(Box::new(Self {
+                crctype: "custom".to_string(),
+                custom: Some(model),
+                identify: None,
+                per_line: args.get_flag("per-line"),
+            }));
+        }
         Ok(Box::new(Self {
             crctype: args.get_one::<String>("type").unwrap().to_string(),
+            custom: None,
+            identify: None,
+            per_line: args.get_flag("per-line"),
         }))
     }
 
+    fn explain(&self) -> String {
+        let mut res = match (&self.custom, self.identify) {
+            (Some(m), _) => format!(
+                "crc: compute a custom {} bits CRC: poly 0x{:x}, init 0x{:x}, refin {}, refout {}, xorout 0x{:x}.",
+                m.width, m.poly, m.init, m.refin, m.refout, m.xorout
+            ),
+            (None, Some(expected)) => format!(
+                "crc: list the known algorithms giving the CRC 0x{:x}, also trying it byte swapped.",
+                expected
+            ),
+            (None, None) if self.crctype == "all" => {
+                format!("crc: compute the {} known CRC algorithms.", ALL_CRCS.len())
+            }
+            (None, None) => format!("crc: compute {}.", self.crctype),
+        };
+        if self.per_line {
+            res.push_str("\nOne CRC is computed for each input line.");
+        }
+        res
+    }
+
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.per_line {
+            return self.per_line(&val);
+        }
+        if let Some(model) = &self.custom {
+            return Ok(model.format(model.checksum(&val)).into_bytes());
+        }
+        if let Some(expected) = self.identify {
+            return self.identify(&val, expected);
+        }
         let alg_name: &str = self.crctype.as_str();
         if alg_name == "all" {
             let mut res = String::new();
@@ -232,6 +401,55 @@ impl Applet for CRCApplet {
 }
 
 impl CRCApplet {
+    /* One "offset crc" line per input line. Lines do not include their
+     * terminating '\n', a final '\n' does not start a new (empty) line */
+    fn per_line(&self, val: &[u8]) -> Result<Vec<u8>> {
+        if self.crctype == "all" {
+            bail!("--per-line cannot be used with 'all'");
+        }
+        let val = val.strip_suffix(b"\n").unwrap_or(val);
+        if val.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut res = String::new();
+        let mut offset = 0;
+        for line in val.split(|c| *c == b'\n') {
+            let crc = match &self.custom {
+                Some(model) => model.format(model.checksum(line)),
+                None => self.do_crc(&self.crctype, line)?,
+            };
+            res.push_str(&format!("{:08x} {}\n", offset, crc));
+            offset += line.len() + 1;
+        }
+        Ok(res.into_bytes())
+    }
+
+    /* List the known algorithms giving the expected value, also trying the
+     * byte swapped value, as a CRC can be stored in little endian */
+    fn identify(&self, val: &[u8], expected: u128) -> Result<Vec<u8>> {
+        let mut res = String::new();
+        for alg in ALL_CRCS.iter() {
+            let crc = u128::from_str_radix(&self.do_crc(alg, val)?, 16)?;
+            let nbytes = (alg.split('_').nth(1).unwrap().parse::<usize>()? + 7) / 8;
+            if crc == expected {
+                res.push_str(alg);
+                res.push('\n');
+            } else if nbytes > 1 && expected >> (nbytes * 8) == 0 {
+                let swapped = expected.to_be_bytes()[16 - nbytes..]
+                    .iter()
+                    .rev()
+                    .fold(0u128, |acc, b| acc << 8 | *b as u128);
+                if crc == swapped {
+                    res.push_str(&format!("{} (byte swapped)\n", alg));
+                }
+            }
+        }
+        if res.is_empty() {
+            bail!("No known CRC algorithm gives 0x{:x}", expected);
+        }
+        Ok(res.into_bytes())
+    }
+
     fn do_crc(&self, alg_name: &str, val: &[u8]) -> Result<String> {
         let alg_size: u8 = (*alg_name.split('_').collect::<Vec<&str>>().get(1).unwrap())
             .parse()
@@ -288,6 +506,7 @@ impl CRCApplet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::applet::build_applet;
 
     #[test]
     fn test_crc32() {
@@ -302,6 +521,9 @@ mod tests {
     fn test_crc() {
         let crc = CRCApplet {
             crctype: "CRC_32_AIXM".to_string(),
+            custom: None,
+            identify: None,
+            per_line: false,
         };
         assert_eq!(
             "fa83f52a".as_bytes().to_vec(),
@@ -317,4 +539,209 @@ mod tests {
             crc16.process_test("toto".as_bytes().to_vec())
         );
     }
+
+    #[test]
+    fn test_custom_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "crc",
+                "--custom",
+                "--width",
+                "8",
+                "--poly",
+                "0x31",
+                "--refin",
+                "--refout",
+                "123456789",
+            ])
+            .assert()
+            .stdout("a1")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["crc", "--custom", "--width", "65", "--poly", "1", "a"])
+            .assert()
+            .failure();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["crc", "--custom", "--width", "8", "--poly", "0x131", "a"])
+            .assert()
+            .stderr(predicates::str::contains("does not fit in 8 bits"))
+            .failure();
+    }
+
+    #[test]
+    fn test_custom() {
+        let check = b"123456789";
+        let crc32 = CrcModel {
+            width: 32,
+            poly: 0x04c11db7,
+            init: 0xffffffff,
+            refin: true,
+            refout: true,
+            xorout: 0xffffffff,
+        };
+        assert_eq!(crc32.checksum(check), 0xcbf43926);
+        let crc = CRCApplet {
+            crctype: "custom".to_string(),
+            custom: Some(crc32),
+            identify: None,
+            per_line: false,
+        };
+        assert_eq!(crc.process_test(b"toto".to_vec()), b"10cca4f1");
+
+        // CRC-3/GSM: width smaller than a byte, output padded to one hex digit
+        let crc3 = CrcModel {
+            width: 3,
+            poly: 0x3,
+            init: 0,
+            refin: false,
+            refout: false,
+            xorout: 0x7,
+        };
+        assert_eq!(crc3.checksum(check), 0x4);
+        assert_eq!(crc3.format(4), "4");
+
+        // CRC-64/XZ
+        let crc64 = CrcModel {
+            width: 64,
+            poly: 0x42f0e1eba9ea3693,
+            init: u64::MAX,
+            refin: true,
+            refout: true,
+            xorout: u64::MAX,
+        };
+        assert_eq!(crc64.checksum(check), 0x995dc9bbdf1939fa);
+    }
+
+    #[test]
+    fn test_reflection() {
+        let check = b"123456789";
+        let model = |poly: u64, refin: bool, refout: bool| CrcModel {
+            width: 16,
+            poly,
+            init: 0,
+            refin,
+            refout,
+            xorout: 0,
+        };
+        // same polynomial, reflected or not: CRC-16/ARC and CRC-16/UMTS,
+        // CRC-16/KERMIT and CRC-16/XMODEM
+        assert_eq!(model(0x8005, true, true).checksum(check), 0xbb3d);
+        assert_eq!(model(0x8005, false, false).checksum(check), 0xfee8);
+        assert_eq!(model(0x1021, true, true).checksum(check), 0x2189);
+        assert_eq!(model(0x1021, false, false).checksum(check), 0x31c3);
+
+        // refout only reflects the final register
+        for (refin, poly) in [(true, 0x8005), (false, 0x8005), (true, 0x1021)] {
+            let crc = model(poly, refin, false).checksum(check);
+            assert_eq!(
+                model(poly, refin, true).checksum(check),
+                (crc as u16).reverse_bits() as u64
+            );
+        }
+
+        // refin reflects each input byte: 0x01 reflected is 0x80
+        assert_eq!(
+            model(0x1021, true, false).checksum(b"\x01\x02"),
+            model(0x1021, false, false).checksum(b"\x80\x40")
+        );
+        assert_ne!(
+            model(0x1021, true, false).checksum(b"\x01\x02"),
+            model(0x1021, false, false).checksum(b"\x01\x02")
+        );
+
+        // reflection happens before xorout
+        let crc32 = |refin: bool, refout: bool| CrcModel {
+            width: 32,
+            poly: 0x04c11db7,
+            init: 0xffffffff,
+            refin,
+            refout,
+            xorout: 0xffffffff,
+        };
+        assert_eq!(crc32(true, true).checksum(check), 0xcbf43926);
+        assert_eq!(crc32(false, false).checksum(check), 0xfc891918);
+        assert_eq!(
+            crc32(true, false).checksum(check),
+            ((crc32(true, true).checksum(check) ^ 0xffffffff) as u32).reverse_bits() as u64
+                ^ 0xffffffff
+        );
+    }
+
+    #[test]
+    fn test_per_line() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["crc", "--per-line", "CRC_32_ISO_HDLC"])
+            .write_stdin("toto\n123456789\n")
+            .assert()
+            .stdout("00000000 10cca4f1\n00000005 cbf43926\n")
+            .success();
+
+        let mut crc = CRCApplet {
+            crctype: "CRC_16_IBM_SDLC".to_string(),
+            custom: None,
+            identify: None,
+            per_line: true,
+        };
+        // empty lines are kept, only a final newline is ignored
+        assert_eq!(
+            crc.process_test(b"toto\n\ntoto".to_vec()),
+            b"00000000 97a8\n00000005 0000\n00000006 97a8\n"
+        );
+        assert_eq!(
+            crc.process_test(b"toto\n\n".to_vec()),
+            b"00000000 97a8\n00000005 0000\n"
+        );
+        assert_eq!(crc.process_test(b"".to_vec()), b"");
+        crc.crctype = "all".to_string();
+        assert!(crc.process(b"toto".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_identify() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["crc", "--identify", "--expected", "CBF43926", "123456789"])
+            .assert()
+            .stdout("CRC_32_ISO_HDLC\n")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["crc", "--identify", "--expected", "0x12345678", "123456789"])
+            .assert()
+            .stderr(predicates::str::contains("No known CRC algorithm"))
+            .failure();
+
+        let crc = CRCApplet {
+            crctype: "identify".to_string(),
+            custom: None,
+            identify: Some(0x2639f4cb),
+            per_line: false,
+        };
+        assert_eq!(
+            crc.process_test(b"123456789".to_vec()),
+            b"CRC_32_ISO_HDLC (byte swapped)\n"
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let explain = |args: &[&str]| {
+            build_applet(CRCApplet::new().as_ref(), args)
+                .unwrap()
+                .explain()
+        };
+        assert_eq!(explain(&["CRC_16_ARC"]), "crc: compute CRC_16_ARC.");
+        assert!(
+            explain(&["--custom", "--width", "8", "--poly", "0x31", "--refin"])
+                .contains("8 bits CRC: poly 0x31, init 0x0, refin true, refout false")
+        );
+        assert!(explain(&["--identify", "--expected", "cbf43926"]).contains("0xcbf43926"));
+    }
 }
@@ -0,0 +1,363 @@
+use crate::applet::{build_applet, Applet, SliceExt};
+use crate::b64app::{B64DecApplet, B64EncApplet};
+use crate::hexapp::{HexApplet, UnHexApplet};
+use crate::urlapp::{UrlDecApplet, UrlEncApplet};
+use crate::xorapp::XorApplet;
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use clap::{arg, Command};
+use serde_json::Value;
+use std::fs;
+
+const B64_STANDARD: &str = "A-Za-z0-9+/=";
+const B64_URL: &str = "A-Za-z0-9-_";
+
+/* Supported operations, for error messages */
+const SUPPORTED_OPS: &str = "To Hex, From Hex, To Base64, From Base64, URL Encode, URL Decode, XOR";
+
+/* A recipe step: an applet, with optional handling of hex delimiters */
+struct Step {
+    applet: Box<dyn Applet>,
+    /* "To Hex": insert a delimiter between bytes */
+    hex_delim: Option<&'static str>,
+    /* "From Hex": remove delimiters and 0x/\x prefixes first */
+    strip_hex_delims: bool,
+    /* "From Base64": valid input chars, others are removed if strip_invalid
+     * is set, rejected otherwise */
+    alphabet: Option<fn(&u8) -> bool>,
+    strip_invalid: bool,
+}
+
+impl Step {
+    fn run(&self, mut val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.strip_hex_delims {
+            let s = String::from_utf8_lossy(&val)
+                .replace("0x", "")
+                .replace("\\x", "");
+            val = s.bytes().filter(|c| c.is_ascii_hexdigit()).collect();
+        }
+        if let Some(valid) = self.alphabet {
+            if self.strip_invalid {
+                val.retain(valid);
+            } else if let Some(c) = val.trim().iter().find(|c| !valid(c)) {
+                bail!("Invalid character {:?} in input", *c as char);
+            }
+        }
+        let res = self.applet.process(val)?;
+        Ok(match self.hex_delim {
+            Some(delim) => res.chunks(2).collect::<Vec<_>>().join(delim.as_bytes()),
+            None => res,
+        })
+    }
+}
+
+/* String argument of an operation, with its default value */
+fn str_arg<'a>(args: &'a [Value], idx: usize, default: &'a str) -> Result<&'a str> {
+    match args.get(idx) {
+        None => Ok(default),
+        Some(Value::String(s)) => Ok(s),
+        Some(v) => bail!("Expected a string argument, got {}", v),
+    }
+}
+
+fn bool_arg(args: &[Value], idx: usize) -> bool {
+    matches!(args.get(idx), Some(Value::Bool(true)))
+}
+
+/* XOR key argument: {"option": "Hex", "string": "41"} */
+fn xor_key(args: &[Value]) -> Result<String> {
+    let key = args.first().context("Missing XOR key")?;
+    let string = key["string"].as_str().context("Invalid XOR key")?;
+    let bytes = match key["option"].as_str().unwrap_or("Hex") {
+        "Hex" => hex::decode(string.replace([' ', ','], "")).context("Invalid hex XOR key")?,
+        "UTF8" | "Latin1" => string.as_bytes().to_vec(),
+        "Base64" => base64::engine::general_purpose::STANDARD
+            .decode(string)
+            .context("Invalid base64 XOR key")?,
+        o => bail!("Unsupported XOR key format \"{}\"", o),
+    };
+    if bytes.is_empty() {
+        bail!("Empty XOR key");
+    }
+    Ok(hex::encode(bytes))
+}
+
+/* Map a CyberChef operation to a step. Returns None if the operation is not
+ * supported at all, an error if its arguments are not */
+fn build_step(op: &str, args: &[Value]) -> Result<Option<Step>> {
+    let app = |app: Box<dyn Applet>, app_args: &[&str]| -> Result<Option<Step>> {
+        Ok(Some(Step {
+            applet: build_applet(app.as_ref(), app_args)?,
+            hex_delim: None,
+            strip_hex_delims: false,
+            alphabet: None,
+            strip_invalid: false,
+        }))
+    };
+    match op {
+        "To Hex" => {
+            let hex_delim = match str_arg(args, 0, "Space")? {
+                "None" => None,
+                "Space" => Some(" "),
+                "Comma" => Some(","),
+                "Semi-colon" => Some(";"),
+                "Colon" => Some(":"),
+                "Line feed" => Some("\n"),
+                "CRLF" => Some("\r\n"),
+                d => bail!("Unsupported \"To Hex\" delimiter \"{}\"", d),
+            };
+            Ok(Some(Step {
+                applet: build_applet(HexApplet::new().as_ref(), &[])?,
+                hex_delim,
+                strip_hex_delims: false,
+                alphabet: None,
+                strip_invalid: false,
+            }))
+        }
+        /* Delimiters are detected, as with CyberChef "Auto" */
+        "From Hex" => Ok(Some(Step {
+            applet: build_applet(UnHexApplet::new().as_ref(), &[])?,
+            hex_delim: None,
+            strip_hex_delims: true,
+            alphabet: None,
+            strip_invalid: false,
+        })),
+        "To Base64" => match str_arg(args, 0, B64_STANDARD)? {
+            B64_STANDARD => app(B64EncApplet::new(), &[]),
+            B64_URL => app(B64EncApplet::new(), &["-u"]),
+            a => bail!("Unsupported base64 alphabet \"{}\"", a),
+        },
+        /* Padding is accepted with both alphabets */
+        "From Base64" => {
+            let (url, alphabet): (bool, fn(&u8) -> bool) = match str_arg(args, 0, B64_STANDARD)? {
+                B64_STANDARD => (false, |c| c.is_ascii_alphanumeric() || b"+/=".contains(c)),
+                B64_URL => (true, |c| c.is_ascii_alphanumeric() || b"-_=".contains(c)),
+                a => bail!("Unsupported base64 alphabet \"{}\"", a),
+            };
+            Ok(Some(Step {
+                applet: build_applet(
+                    B64DecApplet::new().as_ref(),
+                    if url { &["-u"] } else { &[] },
+                )?,
+                hex_delim: None,
+                strip_hex_delims: false,
+                alphabet: Some(alphabet),
+                /* "Remove non-alphabet chars", enabled by default */
+                strip_invalid: args.get(1).and_then(Value::as_bool).unwrap_or(true),
+            }))
+        }
+        /* "Encode all special chars", as encodeURIComponent but also
+         * encoding !'()*, or only those encoded by encodeURI */
+        "URL Encode" => {
+            let unreserved = if bool_arg(args, 0) {
+                "-_.~"
+            } else {
+                "-_.~;,/?:@&=+$!*'()#"
+            };
+            let encoded: String = (0..=255u8)
+                .map(char::from)
+                .filter(|c| !c.is_ascii_alphanumeric() && !unreserved.contains(*c))
+                .collect();
+            app(UrlEncApplet::new(), &["-c", &encoded])
+        }
+        "URL Decode" => app(UrlDecApplet::new(), &[]),
+        "XOR" => {
+            let scheme = str_arg(args, 1, "Standard")?;
+            if scheme != "Standard" || bool_arg(args, 2) {
+                bail!("Only the standard XOR scheme, without null preserving, is supported");
+            }
+            app(XorApplet::new(), &["-x", &xor_key(args)?])
+        }
+        _ => Ok(None),
+    }
+}
+
+/* Parse a recipe, as exported by CyberChef in JSON format:
+ * [{"op": "From Base64", "args": ["A-Za-z0-9+/=", true]}, ...] */
+fn parse_recipe(recipe: &str) -> Result<Vec<Step>> {
+    let recipe: Value = serde_json::from_str(recipe).context("Could not parse recipe JSON")?;
+    let ops = recipe
+        .as_array()
+        .context("Recipe must be an array of operations")?;
+    let mut steps = vec![];
+    let mut unsupported = vec![];
+    for (i, op) in ops.iter().enumerate() {
+        let name = op["op"]
+            .as_str()
+            .with_context(|| format!("Missing operation name in step {}", i + 1))?;
+        if op["disabled"].as_bool() == Some(true) {
+            continue;
+        }
+        let args = op["args"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+        match build_step(name, args).with_context(|| format!("Invalid \"{}\" step", name))? {
+            Some(step) => steps.push(step),
+            None => unsupported.push(name),
+        }
+    }
+    if !unsupported.is_empty() {
+        bail!(
+            "Unsupported operations: {} (supported: {})",
+            unsupported.join(", "),
+            SUPPORTED_OPS
+        );
+    }
+    Ok(steps)
+}
+
+pub struct CyberChefApplet {
+    steps: Vec<Step>,
+}
+
+impl Applet for CyberChefApplet {
+    fn command(&self) -> &'static str {
+        "cyberchef"
+    }
+    fn description(&self) -> &'static str {
+        "run a CyberChef recipe"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-r --recipe <JSON> "recipe, in CyberChef JSON format")
+                    .required_unless_present("file")
+                    .conflicts_with("file"),
+            )
+            .arg(arg!(-f --file <FILE> "file containing the recipe"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(format!(
+                "Supported operations: {}.\nDisabled operations are skipped.",
+                SUPPORTED_OPS
+            ))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { steps: vec![] })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let recipe = match args.get_one::<String>("file") {
+            Some(path) => {
+                fs::read_to_string(path).with_context(|| format!("Could not read file {}", path))?
+            }
+            None => args.get_one::<String>("recipe").unwrap().to_string(),
+        };
+        Ok(Box::new(Self {
+            steps: parse_recipe(&recipe)?,
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        self.steps.iter().try_fold(val, |val, step| step.run(val))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "cyberchef",
+                "-r",
+                r#"[{"op":"From Base64","args":["A-Za-z0-9+/=",true,false]},{"op":"To Hex","args":["Space",0]}]"#,
+                "QUJD",
+            ])
+            .assert()
+            .stdout("41 42 43")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "cyberchef",
+                "-r",
+                r#"[{"op":"ROT13","args":[]},{"op":"To Hex"},{"op":"Gunzip"}]"#,
+                "abc",
+            ])
+            .assert()
+            .stderr(predicates::str::contains(
+                "Unsupported operations: ROT13, Gunzip",
+            ))
+            .failure();
+    }
+
+    #[test]
+    fn test_recipes() {
+        let run = |recipe: &str, val: &[u8]| -> Vec<u8> {
+            let chef = CyberChefApplet {
+                steps: parse_recipe(recipe).unwrap(),
+            };
+            chef.process_test(val.to_vec())
+        };
+        assert_eq!(
+            run(
+                r#"[{"op":"XOR","args":[{"option":"Hex","string":"20"},"Standard",false]},
+                    {"op":"To Base64","args":["A-Za-z0-9+/="]}]"#,
+                b"abc"
+            ),
+            b"QUJD"
+        );
+        assert_eq!(
+            run(
+                r#"[{"op":"URL Decode","args":[]},{"op":"XOR","args":[{"option":"UTF8","string":"k"},"Standard",false]},
+                    {"op":"To Hex","args":["None",0],"disabled":true}]"#,
+                b"%0a%09%08"
+            ),
+            b"abc"
+        );
+        assert_eq!(
+            run(
+                r#"[{"op":"From Hex","args":["Auto"]}]"#,
+                b"41 42:0x43,\\x44"
+            ),
+            b"ABCD"
+        );
+        assert_eq!(
+            run(
+                r#"[{"op":"From Base64","args":["A-Za-z0-9+/=",true]}]"#,
+                b"QU JD\n"
+            ),
+            b"ABC"
+        );
+        assert_eq!(
+            run(
+                r#"[{"op":"From Base64","args":["A-Za-z0-9-_",true]}]"#,
+                b"-_-_!"
+            ),
+            b"\xfb\xff\xbf"
+        );
+        assert_eq!(
+            run(
+                r#"[{"op":"From Base64","args":["A-Za-z0-9+/=",false]}]"#,
+                b"QUJD\n"
+            ),
+            b"ABC"
+        );
+        let chef = CyberChefApplet {
+            steps: parse_recipe(r#"[{"op":"From Base64","args":["A-Za-z0-9+/=",false]}]"#).unwrap(),
+        };
+        assert!(chef.process(b"QU JD".to_vec()).is_err());
+        assert_eq!(
+            run(
+                r#"[{"op":"URL Encode","args":[false]}]"#,
+                b"a b/?&#%\xe9-_.~!*'()"
+            ),
+            b"a%20b/?&#%25%e9-_.~!*'()"
+        );
+        assert_eq!(
+            run(
+                r#"[{"op":"URL Encode","args":[true]}]"#,
+                b"a b/?&#%\xe9-_.~!*'()"
+            ),
+            b"a%20b%2f%3f%26%23%25%e9-_.~%21%2a%27%28%29"
+        );
+        assert!(parse_recipe(r#"[{"op":"To Hex","args":["0x",0]}]"#).is_err());
+        assert!(parse_recipe(r#"{"op":"To Hex"}"#).is_err());
+        assert!(parse_recipe("not json").is_err());
+    }
+}
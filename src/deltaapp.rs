@@ -0,0 +1,85 @@
+use crate::applet::Applet;
+use anyhow::Result;
+use clap::{arg, Command};
+
+pub struct DeltaApplet {
+    reverse: bool,
+}
+
+impl Applet for DeltaApplet {
+    fn command(&self) -> &'static str {
+        "delta"
+    }
+    fn description(&self) -> &'static str {
+        "delta encode/decode (difference between consecutive bytes)"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-r --reverse "decode: compute running sum of the input"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "out[i] = in[i] - in[i-1] (mod 256). The first byte is output as is \
+                 (the previous value is assumed to be 0).",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { reverse: false })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            reverse: args.get_flag("reverse"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let mut prev: u8 = 0;
+        if self.reverse {
+            Ok(val
+                .iter()
+                .map(|b| {
+                    prev = prev.wrapping_add(*b);
+                    prev
+                })
+                .collect())
+        } else {
+            Ok(val
+                .iter()
+                .map(|b| {
+                    let d = b.wrapping_sub(prev);
+                    prev = *b;
+                    d
+                })
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["delta", "ABDA"])
+            .assert()
+            .stdout(&b"\x41\x01\x02\xfd"[..])
+            .success();
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let delta = DeltaApplet { reverse: false };
+        let undelta = DeltaApplet { reverse: true };
+        let data: Vec<u8> = vec![0x10, 0x20, 0x05, 0xff, 0x00, 0x00, 0x80, 0x7f];
+        let encoded = delta.process_test(data.clone());
+        assert_eq!(encoded, [0x10, 0x10, 0xe5, 0xfa, 0x01, 0x00, 0x80, 0xff]);
+        assert_eq!(undelta.process_test(encoded), data);
+        assert_eq!(delta.process_test(vec![]), b"");
+    }
+}
@@ -0,0 +1,262 @@
+use crate::applet::{Applet, Endianness};
+use anyhow::{bail, Result};
+use clap::{arg, value_parser, Command};
+
+pub struct DerefApplet {
+    size: usize,
+    endian: Endianness,
+    keep_prefix: bool,
+    inclusive: bool,
+}
+
+impl Applet for DerefApplet {
+    fn command(&self) -> &'static str {
+        "deref"
+    }
+    fn description(&self) -> &'static str {
+        "extract length-prefixed data"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-s --size <size> "size of the length field, in bytes")
+                    .value_parser(value_parser!(u8).range(1..=8))
+                    .default_value("4"),
+            )
+            .arg(
+                arg!(-e --endian <endian> "endianness of the length field")
+                    .value_parser(clap::builder::EnumValueParser::<Endianness>::new())
+                    .default_value("little"),
+            )
+            .arg(arg!(-k --"keep-prefix" "also output the length field"))
+            .arg(arg!(-i --inclusive "length includes the length field itself"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Reads a length field at the start of the input and outputs the data it describes.\n\
+                 Trailing data is ignored.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            size: 4,
+            endian: Endianness::Little,
+            keep_prefix: false,
+            inclusive: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            size: *args.get_one::<u8>("size").unwrap() as usize,
+            endian: *args.get_one::<Endianness>("endian").unwrap(),
+            keep_prefix: args.get_flag("keep-prefix"),
+            inclusive: args.get_flag("inclusive"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if val.len() < self.size {
+            bail!(
+                "Input too short for a {}-byte length field ({} bytes)",
+                self.size,
+                val.len()
+            );
+        }
+        let len = self.endian.read_uint(&val[..self.size]);
+        let data_len = if self.inclusive {
+            if len < self.size as u64 {
+                bail!(
+                    "Inclusive length ({}) is smaller than the length field ({})",
+                    len,
+                    self.size
+                );
+            }
+            len - self.size as u64
+        } else {
+            len
+        };
+        let available = (val.len() - self.size) as u64;
+        if data_len > available {
+            bail!(
+                "Truncated input: length is {} but only {} bytes are available",
+                data_len,
+                available
+            );
+        }
+        let start = if self.keep_prefix { 0 } else { self.size };
+        Ok(val[start..self.size + data_len as usize].to_vec())
+    }
+}
+
+pub struct PrefixLenApplet {
+    size: usize,
+    endian: Endianness,
+    inclusive: bool,
+}
+
+impl Applet for PrefixLenApplet {
+    fn command(&self) -> &'static str {
+        "prefixlen"
+    }
+    fn description(&self) -> &'static str {
+        "prepend the length of the input"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-s --size <size> "size of the length field, in bytes")
+                    .value_parser(value_parser!(u8).range(1..=8))
+                    .default_value("4"),
+            )
+            .arg(
+                arg!(-e --endian <endian> "endianness of the length field")
+                    .value_parser(clap::builder::EnumValueParser::<Endianness>::new())
+                    .default_value("little"),
+            )
+            .arg(arg!(-i --inclusive "length includes the length field itself"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help("Inverse of deref.")
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            size: 4,
+            endian: Endianness::Little,
+            inclusive: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            size: *args.get_one::<u8>("size").unwrap() as usize,
+            endian: *args.get_one::<Endianness>("endian").unwrap(),
+            inclusive: args.get_flag("inclusive"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let len = if self.inclusive {
+            val.len() + self.size
+        } else {
+            val.len()
+        };
+        let Some(mut res) = self.endian.write_uint(len as u64, self.size) else {
+            bail!(
+                "Length {} does not fit in a {}-byte length field",
+                len,
+                self.size
+            );
+        };
+        res.extend_from_slice(&val);
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["deref", "-s", "2", "-e", "big"])
+            .write_stdin(&b"\x00\x03abcdef"[..])
+            .assert()
+            .stdout("abc")
+            .success();
+    }
+
+    #[test]
+    fn test_exclusive() {
+        let deref = DerefApplet {
+            size: 2,
+            endian: Endianness::Little,
+            keep_prefix: false,
+            inclusive: false,
+        };
+        assert_eq!(
+            deref.process_test(b"\x04\x00testtrailing".to_vec()),
+            b"test"
+        );
+        assert!(deref.process(b"\x05\x00test".to_vec()).is_err());
+        assert!(deref.process(b"\x05".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_inclusive() {
+        let deref = DerefApplet {
+            size: 2,
+            endian: Endianness::Big,
+            keep_prefix: false,
+            inclusive: true,
+        };
+        assert_eq!(
+            deref.process_test(b"\x00\x06testtrailing".to_vec()),
+            b"test"
+        );
+        assert_eq!(deref.process_test(b"\x00\x02test".to_vec()), b"");
+        assert!(deref.process(b"\x00\x01test".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_keep_prefix() {
+        let deref = DerefApplet {
+            size: 1,
+            endian: Endianness::Little,
+            keep_prefix: true,
+            inclusive: true,
+        };
+        assert_eq!(deref.process_test(b"\x03abcdef".to_vec()), b"\x03ab");
+    }
+
+    #[test]
+    fn test_prefixlen() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["prefixlen", "-s", "2", "-e", "big", "abc"])
+            .assert()
+            .stdout(&b"\x00\x03abc"[..])
+            .success();
+
+        let prefix = PrefixLenApplet {
+            size: 2,
+            endian: Endianness::Big,
+            inclusive: true,
+        };
+        assert_eq!(prefix.process_test(b"test".to_vec()), b"\x00\x06test");
+        assert_eq!(prefix.process_test(vec![]), b"\x00\x02");
+
+        let prefix = PrefixLenApplet {
+            size: 1,
+            endian: Endianness::Little,
+            inclusive: false,
+        };
+        assert_eq!(prefix.process_test(vec![0x41; 255])[0], 0xff);
+        assert!(prefix.process(vec![0x41; 256]).is_err());
+
+        let prefix = PrefixLenApplet {
+            size: 8,
+            endian: Endianness::Little,
+            inclusive: false,
+        };
+        assert_eq!(prefix.process_test(b"a".to_vec()), b"\x01\0\0\0\0\0\0\0a");
+
+        // roundtrip with deref
+        let deref = DerefApplet {
+            size: 8,
+            endian: Endianness::Little,
+            keep_prefix: false,
+            inclusive: false,
+        };
+        assert_eq!(
+            deref.process_test(prefix.process_test(b"data".to_vec())),
+            b"data"
+        );
+    }
+}
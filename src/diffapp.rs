@@ -0,0 +1,315 @@
+use crate::applet::Applet;
+use anyhow::{Context, Result};
+use clap::{arg, value_parser, Command};
+use std::fs;
+
+/* Bytes per line in side-by-side mode */
+const SBS_LINE_LEN: usize = 8;
+
+pub struct DiffApplet {
+    with: Vec<u8>,
+    side_by_side: bool,
+    /* Coalesce differing bytes into runs, with this many context bytes */
+    runs: Option<usize>,
+}
+
+impl DiffApplet {
+    fn fmt_byte(b: Option<&u8>) -> String {
+        match b {
+            Some(b) => format!("{:02x}", b),
+            None => "--".to_string(),
+        }
+    }
+
+    /* One line per differing byte: offset, input byte, other byte.
+     * Bytes past the end of the shortest buffer are shown as "--" */
+    fn diff_list(&self, val: &[u8]) -> String {
+        let mut res = String::new();
+        for i in 0..val.len().max(self.with.len()) {
+            let (a, b) = (val.get(i), self.with.get(i));
+            if a != b {
+                res.push_str(&format!(
+                    "{:08x}: {} {}\n",
+                    i,
+                    Self::fmt_byte(a),
+                    Self::fmt_byte(b)
+                ));
+            }
+        }
+        res
+    }
+
+    /* Hex bytes of buf in [start, end), bytes past its end are shown as "--" */
+    fn hex_range(buf: &[u8], start: usize, end: usize) -> String {
+        (start..end).map(|i| Self::fmt_byte(buf.get(i))).collect()
+    }
+
+    /* Runs of consecutive differing bytes, as (start, end) offsets */
+    fn diff_ranges(&self, val: &[u8]) -> Vec<(usize, usize)> {
+        let mut res: Vec<(usize, usize)> = vec![];
+        for i in 0..val.len().max(self.with.len()) {
+            if val.get(i) != self.with.get(i) {
+                match res.last_mut() {
+                    Some(last) if last.1 == i => last.1 = i + 1,
+                    _ => res.push((i, i + 1)),
+                }
+            }
+        }
+        res
+    }
+
+    /* One line per run: offset, length, input bytes, other bytes. Context
+     * bytes are shown around the run, which is between brackets. Runs whose
+     * contexts overlap are shown on the same line, so that no byte is shown
+     * twice and differing bytes are always between brackets */
+    fn diff_runs(&self, val: &[u8], context: usize) -> String {
+        let mut groups: Vec<Vec<(usize, usize)>> = vec![];
+        for (start, end) in self.diff_ranges(val) {
+            match groups.last_mut() {
+                Some(group) if start - group.last().unwrap().1 < 2 * context => {
+                    group.push((start, end))
+                }
+                _ => groups.push(vec![(start, end)]),
+            }
+        }
+        let mut res = String::new();
+        for group in groups {
+            let start = group.first().unwrap().0;
+            let end = group.last().unwrap().1;
+            res.push_str(&format!("{:08x} +{}:", start, end - start));
+            for buf in [val, &self.with] {
+                res.push(' ');
+                if context == 0 {
+                    res.push_str(&Self::hex_range(buf, start, end));
+                    continue;
+                }
+                /* Context stops at the buffer boundaries */
+                let before = start.saturating_sub(context);
+                let after = (end + context).min(buf.len()).max(end);
+                res.push_str(&Self::hex_range(buf, before, start));
+                let mut prev_end = start;
+                for (run_start, run_end) in group.iter() {
+                    res.push_str(&Self::hex_range(buf, prev_end, *run_start));
+                    res.push('[');
+                    res.push_str(&Self::hex_range(buf, *run_start, *run_end));
+                    res.push(']');
+                    prev_end = *run_end;
+                }
+                res.push_str(&Self::hex_range(buf, end, after));
+            }
+            res.push('\n');
+        }
+        res
+    }
+
+    /* hexdump of a line of one buffer, differing bytes are prefixed with '*' */
+    fn sbs_half(line: &[u8], other: &[u8], res: &mut String) {
+        for j in 0..SBS_LINE_LEN {
+            match line.get(j) {
+                Some(b) => {
+                    res.push(if other.get(j) != Some(b) { '*' } else { ' ' });
+                    res.push_str(&format!("{:02x}", b));
+                }
+                None => res.push_str("   "),
+            }
+        }
+        res.push_str("  |");
+        for j in 0..SBS_LINE_LEN {
+            res.push(match line.get(j) {
+                Some(c) if c.is_ascii_graphic() || *c == b' ' => *c as char,
+                Some(_) => '.',
+                None => ' ',
+            });
+        }
+        res.push('|');
+    }
+
+    fn side_by_side(&self, val: &[u8]) -> String {
+        let mut res = String::new();
+        let len = val.len().max(self.with.len());
+        for off in (0..len).step_by(SBS_LINE_LEN) {
+            let line = |buf: &[u8]| -> Vec<u8> {
+                buf.iter().skip(off).take(SBS_LINE_LEN).copied().collect()
+            };
+            let (a, b) = (line(val), line(&self.with));
+            res.push_str(&format!("{:08x} ", off));
+            Self::sbs_half(&a, &b, &mut res);
+            res.push_str("  ");
+            Self::sbs_half(&b, &a, &mut res);
+            res.push('\n');
+        }
+        res
+    }
+}
+
+impl Applet for DiffApplet {
+    fn command(&self) -> &'static str {
+        "diff"
+    }
+    fn description(&self) -> &'static str {
+        "binary diff between input and a file"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-w --with <FILE> "file to compare the input with").required(true))
+            .arg(arg!(-s --"side-by-side" "show a side-by-side hexdump, differing bytes are marked with '*'"))
+            .arg(
+                arg!(-r --runs "coalesce consecutive differing bytes into runs")
+                    .conflicts_with("side-by-side"),
+            )
+            .arg(
+                arg!(-C --context <N> "show N matching bytes around runs (implies --runs)")
+                    .value_parser(value_parser!(u64))
+                    .conflicts_with("side-by-side"),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "By default, outputs one line per differing byte: offset, input byte, file byte.\n\
+                 With --runs, outputs one line per run: offset, +length, input bytes, file bytes.\n\
+                 With --context N, runs less than 2N bytes apart are shown on the same line.\n\
+                 Bytes past the end of the shortest buffer are shown as \"--\".",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            with: vec![],
+            side_by_side: false,
+            runs: None,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let path = args.get_one::<String>("with").unwrap();
+        let with = fs::read(path).with_context(|| format!("Could not read file {}", path))?;
+        Ok(Box::new(Self {
+            with,
+            side_by_side: args.get_flag("side-by-side"),
+            runs: match args.get_one::<u64>("context") {
+                Some(n) => Some(*n as usize),
+                None => args.get_flag("runs").then_some(0),
+            },
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let res = if self.side_by_side {
+            self.side_by_side(&val)
+        } else if let Some(context) = self.runs {
+            self.diff_runs(&val, context)
+        } else {
+            self.diff_list(&val)
+        };
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_cli() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        tmp.write_all(b"ABCDEF").unwrap();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["diff", "-w", tmp.path().to_str().unwrap(), "ABXD"])
+            .assert()
+            .stdout("00000002: 58 43\n00000004: -- 45\n00000005: -- 46\n")
+            .success();
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let diff = DiffApplet {
+            with: b"ABCDEFGHIJ".to_vec(),
+            side_by_side: true,
+            runs: None,
+        };
+        assert_eq!(
+            String::from_utf8(diff.process_test(b"ABxDEFGH\x00".to_vec())).unwrap(),
+            "00000000  41 42*78 44 45 46 47 48  |ABxDEFGH|   41 42*43 44 45 46 47 48  |ABCDEFGH|\n\
+             00000008 *00                       |.       |  *49*4a                    |IJ      |\n"
+        );
+
+        let same = DiffApplet {
+            with: b"AB".to_vec(),
+            side_by_side: false,
+            runs: None,
+        };
+        assert_eq!(same.process_test(b"AB".to_vec()), b"");
+    }
+
+    #[test]
+    fn test_runs() {
+        let diff = |with: &[u8], runs: usize, val: &[u8]| {
+            let app = DiffApplet {
+                with: with.to_vec(),
+                side_by_side: false,
+                runs: Some(runs),
+            };
+            String::from_utf8(app.process_test(val.to_vec())).unwrap()
+        };
+        // contiguous 3-byte change is a single run
+        assert_eq!(
+            diff(b"ABCDEFGH", 0, b"ABxyzFGH"),
+            "00000002 +3: 78797a 434445\n"
+        );
+        assert_eq!(
+            diff(b"ABCDEFGH", 0, b"xBCDEFGy"),
+            "00000000 +1: 78 41\n00000007 +1: 79 48\n"
+        );
+
+        // context, clipped at the buffer boundaries
+        assert_eq!(
+            diff(b"ABCDEFGH", 2, b"ABxyzFGH"),
+            "00000002 +3: 4142[78797a]4647 4142[434445]4647\n"
+        );
+        assert_eq!(
+            diff(b"ABCD", 2, b"xBCy"),
+            "00000000 +4: [78]4243[79] [41]4243[44]\n"
+        );
+
+        // runs closer than twice the context share a line
+        assert_eq!(
+            diff(b"ABCDEFGH", 3, b"xBCyEFGH"),
+            "00000000 +4: [78]4243[79]454647 [41]4243[44]454647\n"
+        );
+        assert_eq!(
+            diff(b"ABCDEFGH", 1, b"xBCyEFGH"),
+            "00000000 +1: [78]42 [41]42\n00000003 +1: 43[79]45 43[44]45\n"
+        );
+
+        // different lengths
+        assert_eq!(
+            diff(b"ABCDEF", 1, b"ABCD"),
+            "00000004 +2: 44[----] 44[4546]\n"
+        );
+        assert_eq!(diff(b"AB", 1, b"AB"), "");
+    }
+
+    #[test]
+    fn test_runs_cli() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        tmp.write_all(b"ABCDEF").unwrap();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "diff",
+                "-w",
+                tmp.path().to_str().unwrap(),
+                "--context",
+                "1",
+                "AxyD",
+            ])
+            .assert()
+            .stdout("00000001 +5: 41[7879]44[----] 41[4243]44[4546]\n")
+            .success();
+    }
+}
@@ -0,0 +1,171 @@
+use crate::applet::{Applet, SliceExt};
+use anyhow::{bail, Result};
+use clap::{arg, Command};
+
+const MAX_LABEL_LEN: usize = 63;
+const MAX_NAME_LEN: usize = 255;
+
+pub struct DnsNameApplet {
+    reverse: bool,
+}
+
+impl DnsNameApplet {
+    /* "example.com" => "\x07example\x03com\x00" */
+    fn encode(name: &[u8]) -> Result<Vec<u8>> {
+        let name = name.strip_suffix(b".").unwrap_or(name);
+        let mut res = Vec::with_capacity(name.len() + 2);
+        if !name.is_empty() {
+            for label in name.split(|c| *c == b'.') {
+                if label.is_empty() {
+                    bail!("Empty label in name");
+                }
+                if label.len() > MAX_LABEL_LEN {
+                    bail!(
+                        "Label \"{}\" is {} bytes long, maximum is {}",
+                        String::from_utf8_lossy(label),
+                        label.len(),
+                        MAX_LABEL_LEN
+                    );
+                }
+                res.push(label.len() as u8);
+                res.extend_from_slice(label);
+            }
+        }
+        res.push(0);
+        if res.len() > MAX_NAME_LEN {
+            bail!(
+                "Encoded name is {} bytes long, maximum is {}",
+                res.len(),
+                MAX_NAME_LEN
+            );
+        }
+        Ok(res)
+    }
+
+    /* Labels up to the terminating zero byte, trailing data is ignored */
+    fn decode(val: &[u8]) -> Result<Vec<u8>> {
+        let mut labels: Vec<&[u8]> = vec![];
+        let mut off = 0;
+        loop {
+            let len = match val.get(off) {
+                Some(0) => break,
+                Some(l) if *l as usize > MAX_LABEL_LEN => bail!(
+                    "Invalid label length 0x{:02x} at offset {} (compression pointers are not supported)",
+                    l,
+                    off
+                ),
+                Some(l) => *l as usize,
+                None => bail!("Truncated name: missing terminating zero byte"),
+            };
+            match val.get(off + 1..off + 1 + len) {
+                Some(label) => labels.push(label),
+                None => bail!("Truncated label at offset {}", off),
+            }
+            off += 1 + len;
+        }
+        if labels.is_empty() {
+            return Ok(b".".to_vec());
+        }
+        Ok(labels.join(&b'.'))
+    }
+}
+
+impl Applet for DnsNameApplet {
+    fn command(&self) -> &'static str {
+        "dnsname"
+    }
+    fn description(&self) -> &'static str {
+        "encode a domain name to DNS wire format (length-prefixed labels)"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-r --reverse "decode a wire format name to dotted notation"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "The root name is \".\" or empty. When decoding, data after the terminating \
+                 zero byte is ignored.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { reverse: false })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            reverse: args.get_flag("reverse"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.reverse {
+            Self::decode(&val)
+        } else {
+            Self::encode(val.trim())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["dnsname"])
+            .write_stdin("example.com\n")
+            .assert()
+            .stdout("\x07example\x03com\x00")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["dnsname", "-r"])
+            .write_stdin("\x07example\x03com\x00\x00\x01")
+            .assert()
+            .stdout("example.com")
+            .success();
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let enc = DnsNameApplet { reverse: false };
+        let dec = DnsNameApplet { reverse: true };
+        for name in ["example.com", "www.example.com", "a.b.c.d.e"] {
+            let wire = enc.process_test(name.as_bytes().to_vec());
+            assert_eq!(dec.process_test(wire), name.as_bytes());
+        }
+        assert_eq!(
+            enc.process_test(b"example.com.".to_vec()),
+            b"\x07example\x03com\x00"
+        );
+
+        // root
+        assert_eq!(enc.process_test(b"".to_vec()), b"\x00");
+        assert_eq!(enc.process_test(b".".to_vec()), b"\x00");
+        assert_eq!(dec.process_test(b"\x00".to_vec()), b".");
+    }
+
+    #[test]
+    fn test_invalid() {
+        let enc = DnsNameApplet { reverse: false };
+        let label63 = "a".repeat(63);
+        assert!(enc.process(format!("{}.com", label63).into_bytes()).is_ok());
+        assert!(enc
+            .process(format!("a{}.com", label63).into_bytes())
+            .is_err());
+        assert!(enc.process(b"example..com".to_vec()).is_err());
+        let long = [label63.as_str(); 4].join(".");
+        assert!(enc.process(long.into_bytes()).is_err());
+
+        let dec = DnsNameApplet { reverse: true };
+        assert!(dec.process(b"\x07example\x03com".to_vec()).is_err());
+        assert!(dec.process(b"\x07exam".to_vec()).is_err());
+        assert!(dec.process(b"\xc0\x0c".to_vec()).is_err());
+        assert!(dec.process(vec![]).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::applet::Applet;
+use anyhow::Result;
+use clap::{arg, Command};
+
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug, PartialEq)]
+pub enum Lang {
+    #[default]
+    C,
+    Python,
+    Rust,
+    Js,
+}
+
+pub struct EmitApplet {
+    lang: Lang,
+}
+
+impl EmitApplet {
+    fn hex_list(val: &[u8], sep: &str) -> String {
+        val.iter()
+            .map(|b| format!("0x{:02x}", b))
+            .collect::<Vec<String>>()
+            .join(sep)
+    }
+}
+
+impl Applet for EmitApplet {
+    fn command(&self) -> &'static str {
+        "emit"
+    }
+    fn description(&self) -> &'static str {
+        "render input as a source code literal"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-l --lang <lang> "target language")
+                    .value_parser(clap::builder::EnumValueParser::<Lang>::new())
+                    .default_value("c"),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Examples for \"AB\":\n  \
+                 c:      {0x41,0x42}\n  \
+                 python: b\"\\x41\\x42\"\n  \
+                 rust:   &[0x41, 0x42]\n  \
+                 js:     new Uint8Array([0x41, 0x42])",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { lang: Lang::C })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            lang: *args.get_one::<Lang>("lang").unwrap(),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let res = match self.lang {
+            Lang::C => format!("{{{}}}", Self::hex_list(&val, ",")),
+            Lang::Python => {
+                let escaped: String = val.iter().map(|b| format!("\\x{:02x}", b)).collect();
+                format!("b\"{}\"", escaped)
+            }
+            Lang::Rust => format!("&[{}]", Self::hex_list(&val, ", ")),
+            Lang::Js => format!("new Uint8Array([{}])", Self::hex_list(&val, ", ")),
+        };
+        Ok(res.into_bytes())
+    }
+
+    fn returns_text(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["emit", "AB"])
+            .assert()
+            .stdout("{0x41,0x42}")
+            .success();
+    }
+
+    #[test]
+    fn test_langs() {
+        let python = EmitApplet { lang: Lang::Python };
+        assert_eq!(
+            python.process_test(b"AB\x00\xff".to_vec()),
+            b"b\"\\x41\\x42\\x00\\xff\""
+        );
+        assert_eq!(python.process_test(vec![]), b"b\"\"");
+
+        let rust = EmitApplet { lang: Lang::Rust };
+        assert_eq!(rust.process_test(b"AB".to_vec()), b"&[0x41, 0x42]");
+        assert_eq!(rust.process_test(vec![]), b"&[]");
+
+        let js = EmitApplet { lang: Lang::Js };
+        assert_eq!(js.process_test(b"\n".to_vec()), b"new Uint8Array([0x0a])");
+    }
+}
@@ -1,7 +1,17 @@
 use crate::applet::Applet;
 use anyhow::Result;
+use clap::{arg, value_parser, Command};
 
-pub struct EntropyApplet {}
+/* Sliding window scan parameters */
+struct Scan {
+    window: usize,
+    step: usize,
+    threshold: f64,
+}
+
+pub struct EntropyApplet {
+    scan: Option<Scan>,
+}
 
 fn entropy(val: &[u8]) -> f64 {
     if val.is_empty() {
@@ -26,6 +36,26 @@ fn entropy(val: &[u8]) -> f64 {
     res
 }
 
+/* Ranges (start, end) where the entropy of the window is above the threshold,
+ * overlapping or adjacent windows are merged */
+fn scan_ranges(val: &[u8], scan: &Scan) -> Vec<(usize, usize)> {
+    let mut res: Vec<(usize, usize)> = vec![];
+    let mut start = 0;
+    loop {
+        let end = (start + scan.window).min(val.len());
+        if end > start && entropy(&val[start..end]) >= scan.threshold {
+            match res.last_mut() {
+                Some(last) if last.1 >= start => last.1 = end,
+                _ => res.push((start, end)),
+            }
+        }
+        if end == val.len() {
+            return res;
+        }
+        start += scan.step;
+    }
+}
+
 impl Applet for EntropyApplet {
     fn command(&self) -> &'static str {
         "entropy"
@@ -34,15 +64,58 @@ impl Applet for EntropyApplet {
         "compute file entropy"
     }
 
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-s --scan "locate high entropy (compressed, encrypted) regions with a sliding window"))
+            .arg(
+                arg!(-w --window <SIZE> "window size for --scan")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .default_value("1024"),
+            )
+            .arg(
+                arg!(--step <SIZE> "window step for --scan")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .default_value("256"),
+            )
+            .arg(
+                arg!(-t --threshold <ENTROPY> "minimum entropy for --scan, between 0 and 1")
+                    .value_parser(value_parser!(f64))
+                    .default_value("0.9"),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "--scan outputs \"0xstart-0xend: entropy\" lines, end excluded. Windows \
+                 smaller than 256 bytes cannot reach high entropy values.",
+            )
+    }
+
     fn new() -> Box<dyn Applet> {
-        Box::new(Self {})
+        Box::new(Self { scan: None })
     }
 
-    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
-        Ok(Box::new(Self {}))
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let scan = args.get_flag("scan").then(|| Scan {
+            window: *args.get_one::<u64>("window").unwrap() as usize,
+            step: *args.get_one::<u64>("step").unwrap() as usize,
+            threshold: *args.get_one::<f64>("threshold").unwrap(),
+        });
+        Ok(Box::new(Self { scan }))
     }
 
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if let Some(scan) = &self.scan {
+            let mut res = String::new();
+            for (start, end) in scan_ranges(&val, scan) {
+                res.push_str(&format!(
+                    "0x{:x}-0x{:x}: {:.3}\n",
+                    start,
+                    end,
+                    entropy(&val[start..end])
+                ));
+            }
+            return Ok(res.into_bytes());
+        }
         Ok(format!("{:.3}", entropy(val.as_slice()))
             .as_bytes()
             .to_vec())
@@ -59,7 +132,7 @@ mod tests {
 
     #[test]
     fn test() {
-        let ent = EntropyApplet {};
+        let ent = EntropyApplet { scan: None };
         assert_eq!(run_ent(&ent, Vec::new()), "0.000");
         assert_eq!(run_ent(&ent, vec![1, 2, 3, 4]), "0.250");
         let mut all_bytes: Vec<u8> = Vec::with_capacity(256);
@@ -68,4 +141,52 @@ mod tests {
         }
         assert_eq!(run_ent(&ent, all_bytes), "0.999");
     }
+
+    #[test]
+    fn test_scan() {
+        /* 1024 null bytes, 2048 random bytes, 1024 null bytes */
+        let mut data = vec![0u8; 4096];
+        let mut x: u32 = 0x12345678;
+        for b in data[1024..3072].iter_mut() {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            *b = x as u8;
+        }
+        let ent = EntropyApplet {
+            scan: Some(Scan {
+                window: 1024,
+                step: 256,
+                threshold: 0.9,
+            }),
+        };
+        let res = String::from_utf8(ent.process_test(data.clone())).unwrap();
+        assert!(res.starts_with("0x400-0xc00: 0.9"), "{}", res);
+        assert_eq!(res.lines().count(), 1);
+
+        // nothing above the threshold
+        assert!(ent.process_test(vec![0; 4096]).is_empty());
+
+        // input smaller than the window is scanned as a whole
+        let ent = EntropyApplet {
+            scan: Some(Scan {
+                window: 1024,
+                step: 256,
+                threshold: 0.5,
+            }),
+        };
+        let res = String::from_utf8(ent.process_test(data[1024..1536].to_vec())).unwrap();
+        assert!(res.starts_with("0x0-0x200: "), "{}", res);
+    }
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["entropy", "--scan", "-w", "4", "--step", "2", "-t", "0.2"])
+            .write_stdin("\0\0\0\0\0\0abcd\0\0\0\0\0\0")
+            .assert()
+            .stdout("0x6-0xa: 0.250\n")
+            .success();
+    }
 }
@@ -151,14 +151,15 @@ impl Applet for EscapeApplet {
         };
 
         let escaped = to_escape_nq.escape(&self.esc_type);
-        if self.no_quote || quote.is_none() {
-            Ok(escaped)
-        } else {
-            let mut res = Vec::<u8>::with_capacity(escaped.len() + 2);
-            res.push(quote.unwrap());
-            res.extend(escaped);
-            res.push(quote.unwrap());
-            Ok(res)
+        match quote {
+            Some(q) if !self.no_quote => {
+                let mut res = Vec::<u8>::with_capacity(escaped.len() + 2);
+                res.push(q);
+                res.extend(escaped);
+                res.push(q);
+                Ok(res)
+            }
+            _ => Ok(escaped),
         }
     }
 
@@ -281,7 +282,7 @@ mod tests {
     fn test_base_escape_arg_auto() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", r"te'st"])
+            .args(["escape", r"te'st"])
             .assert()
             .stdout(r#""te\'st""#)
             .success();
@@ -291,7 +292,7 @@ mod tests {
     fn test_base_escape_stdin_auto() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape"])
+            .args(["escape"])
             .write_stdin("'te'st'\n") // by default, trim input so '\n' will be removed
             .assert()
             .stdout(r"'te\'st'")
@@ -302,7 +303,7 @@ mod tests {
     fn test_base_escape_stdin_no_detect() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-d"])
+            .args(["escape", "-d"])
             // no detect mode will not try to determine enclosing quote type,
             // just escape them
             .write_stdin(r"'test'")
@@ -315,7 +316,7 @@ mod tests {
     fn test_base_escape_stdin_auto_multiline() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-m"])
+            .args(["escape", "-m"])
             // multiline mode will not trim '\n', escaping them instead
             .write_stdin("te'st\nte\"st\n")
             .assert()
@@ -327,7 +328,7 @@ mod tests {
     fn test_base_escape_stdin_bash_single() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-t", "bash-single"])
+            .args(["escape", "-t", "bash-single"])
             .write_stdin("te'st")
             .assert()
             .stdout(r#"'te'"'"'st'"#)
@@ -338,7 +339,7 @@ mod tests {
     fn test_base_escape_stdin_bash() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-t", "bash"])
+            .args(["escape", "-t", "bash"])
             .write_stdin(r#""!t"e`s$t""#)
             .assert()
             .stdout(r#""\!t\"e\`s\$t""#)
@@ -349,7 +350,7 @@ mod tests {
     fn test_base_escape_stdin_posix_shell() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-t", "shell"])
+            .args(["escape", "-t", "shell"])
             .write_stdin(r#""!t"e`s$t""#)
             .assert()
             .stdout(r#""!t\"e\`s\$t""#)
@@ -360,7 +361,7 @@ mod tests {
     fn test_base_escape_stdin_single() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-t", "single"])
+            .args(["escape", "-t", "single"])
             .write_stdin(r#"sin'gle"#)
             .assert()
             .stdout(r#"'sin\'gle'"#)
@@ -371,7 +372,7 @@ mod tests {
     fn test_base_escape_stdin_single_noquote() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["escape", "-t", "single", "-n"])
+            .args(["escape", "-t", "single", "-n"])
             .write_stdin(r#"sin'gle"#)
             .assert()
             .stdout(r#"sin\'gle"#)
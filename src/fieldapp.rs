@@ -0,0 +1,157 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, value_parser, Command};
+
+pub struct FieldApplet {
+    sep: Vec<u8>,
+    index: i64,
+    strict: bool,
+}
+
+impl FieldApplet {
+    /* Parse separator: hex if prefixed with 0x, else a literal string */
+    fn parse_sep(sep: &str) -> Result<Vec<u8>> {
+        let res = match sep.strip_prefix("0x") {
+            Some(h) => hex::decode(h).with_context(|| "Invalid hex separator")?,
+            None => sep.as_bytes().to_vec(),
+        };
+        if res.is_empty() {
+            bail!("Separator cannot be empty");
+        }
+        Ok(res)
+    }
+
+    fn split<'a>(&self, val: &'a [u8]) -> Vec<&'a [u8]> {
+        let mut fields = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i + self.sep.len() <= val.len() {
+            if val[i..].starts_with(&self.sep) {
+                fields.push(&val[start..i]);
+                i += self.sep.len();
+                start = i;
+            } else {
+                i += 1;
+            }
+        }
+        fields.push(&val[start..]);
+        fields
+    }
+}
+
+impl Applet for FieldApplet {
+    fn command(&self) -> &'static str {
+        "field"
+    }
+    fn description(&self) -> &'static str {
+        "split input on a separator and output a field"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-s --sep <SEP> "separator: string, or hex if prefixed with 0x (ex: 0x0d0a)")
+                    .required(true),
+            )
+            .arg(
+                arg!(-i --index <N> "index of the field, starting at 0, relative to the end if negative")
+                    .value_parser(value_parser!(i64))
+                    .allow_negative_numbers(true)
+                    .required(true),
+            )
+            .arg(arg!(--strict "error if the index is out of range"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Empty fields are kept. If the index is out of range, the output is empty, \
+                 unless --strict is given.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            sep: vec![],
+            index: 0,
+            strict: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            sep: Self::parse_sep(args.get_one::<String>("sep").unwrap())?,
+            index: *args.get_one::<i64>("index").unwrap(),
+            strict: args.get_flag("strict"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let fields = self.split(&val);
+        let count = fields.len() as i64;
+        let idx = if self.index < 0 {
+            count + self.index
+        } else {
+            self.index
+        };
+        if idx < 0 || idx >= count {
+            if self.strict {
+                bail!(
+                    "Index {} out of range, input has {} fields",
+                    self.index,
+                    count
+                );
+            }
+            return Ok(vec![]);
+        }
+        Ok(fields[idx as usize].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["field", "-s", "0x0d0a", "-i", "-1"])
+            .write_stdin("a\r\nb\nc\r\nlast")
+            .assert()
+            .stdout("last")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["field", "-s", ",", "-i", "3", "--strict", "a,b,c"])
+            .assert()
+            .stderr(predicates::str::contains("out of range"))
+            .failure();
+    }
+
+    #[test]
+    fn test_field() {
+        let field = |index: i64| FieldApplet {
+            sep: b"::".to_vec(),
+            index,
+            strict: false,
+        };
+        let data = b"a::b\x00:::c::".to_vec();
+        assert_eq!(field(0).process_test(data.clone()), b"a");
+        assert_eq!(field(1).process_test(data.clone()), b"b\x00");
+        assert_eq!(field(2).process_test(data.clone()), b":c");
+        assert_eq!(field(3).process_test(data.clone()), b"");
+        assert_eq!(field(-2).process_test(data.clone()), b":c");
+        assert_eq!(field(4).process_test(data.clone()), b"");
+        assert_eq!(field(-5).process_test(data.clone()), b"");
+        assert_eq!(field(0).process_test(b"no sep".to_vec()), b"no sep");
+
+        let strict = FieldApplet {
+            sep: b"::".to_vec(),
+            index: -5,
+            strict: true,
+        };
+        assert!(strict.process(data).is_err());
+        assert!(FieldApplet::parse_sep("0x").is_err());
+        assert_eq!(FieldApplet::parse_sep("0x00ff").unwrap(), b"\x00\xff");
+    }
+}
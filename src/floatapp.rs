@@ -0,0 +1,162 @@
+use crate::applet::{Applet, Endianness, SliceExt};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+pub struct FloatApplet {
+    size: usize,
+    endian: Endianness,
+    reverse: bool,
+}
+
+impl FloatApplet {
+    fn decode(&self, chunk: &[u8]) -> String {
+        let bits = self.endian.read_uint(chunk);
+        let (val, is_nan) = if self.size == 4 {
+            let f = f32::from_bits(bits as u32);
+            (format!("{:?}", f), f.is_nan())
+        } else {
+            let f = f64::from_bits(bits);
+            (format!("{:?}", f), f.is_nan())
+        };
+        // NaN can have many representations, show which one was used
+        if is_nan {
+            format!("{} (0x{:0width$x})", val, bits, width = self.size * 2)
+        } else {
+            val
+        }
+    }
+
+    fn encode(&self, val: &str) -> Result<Vec<u8>> {
+        let bytes = if self.size == 4 {
+            let f: f32 = val.parse().with_context(|| "Invalid float value")?;
+            f.to_le_bytes().to_vec()
+        } else {
+            let f: f64 = val.parse().with_context(|| "Invalid float value")?;
+            f.to_le_bytes().to_vec()
+        };
+        Ok(match self.endian {
+            Endianness::Little => bytes,
+            Endianness::Big => bytes.into_iter().rev().collect(),
+        })
+    }
+}
+
+impl Applet for FloatApplet {
+    fn command(&self) -> &'static str {
+        "float"
+    }
+    fn description(&self) -> &'static str {
+        "decode/encode IEEE-754 floats"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-s --size <size> "size in bytes: 4 (float) or 8 (double)")
+                    .value_parser(["4", "8"])
+                    .default_value("4"),
+            )
+            .arg(
+                arg!(-e --endian <endian> "endianness")
+                    .value_parser(clap::builder::EnumValueParser::<Endianness>::new())
+                    .default_value("little"),
+            )
+            .arg(arg!(-r --reverse "encode the textual float given as input"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help("When decoding, the input must be a multiple of the size, one value is output per line.")
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            size: 4,
+            endian: Endianness::Little,
+            reverse: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            size: args.get_one::<String>("size").unwrap().parse()?,
+            endian: *args.get_one::<Endianness>("endian").unwrap(),
+            reverse: args.get_flag("reverse"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.reverse {
+            let s = String::from_utf8_lossy(val.trim());
+            return self.encode(&s);
+        }
+        if val.is_empty() || val.len() % self.size != 0 {
+            bail!(
+                "Input length ({}) is not a multiple of {}",
+                val.len(),
+                self.size
+            );
+        }
+        let res: Vec<String> = val.chunks(self.size).map(|c| self.decode(c)).collect();
+        Ok(res.join("\n").into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["float", "-s", "8", "-e", "big"])
+            .write_stdin(&b"\x3f\xf8\x00\x00\x00\x00\x00\x00\x7f\xf0\x00\x00\x00\x00\x00\x00"[..])
+            .assert()
+            .stdout("1.5\ninf")
+            .success();
+    }
+
+    #[test]
+    fn test_f32_roundtrip() {
+        let enc = FloatApplet {
+            size: 4,
+            endian: Endianness::Little,
+            reverse: true,
+        };
+        let dec = FloatApplet {
+            size: 4,
+            endian: Endianness::Little,
+            reverse: false,
+        };
+        let packed = enc.process_test(b"1.5\n".to_vec());
+        assert_eq!(packed, b"\x00\x00\xc0\x3f");
+        assert_eq!(dec.process_test(packed), b"1.5");
+        assert_eq!(enc.process_test(b"-inf".to_vec()), b"\x00\x00\x80\xff");
+        assert_eq!(
+            dec.process_test(b"\x01\x00\xc0\x7f".to_vec()),
+            b"NaN (0x7fc00001)"
+        );
+        assert!(dec.process(b"\x00\x00\xc0".to_vec()).is_err());
+        assert!(enc.process(b"1.5x".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_f64_roundtrip() {
+        let enc = FloatApplet {
+            size: 8,
+            endian: Endianness::Big,
+            reverse: true,
+        };
+        let dec = FloatApplet {
+            size: 8,
+            endian: Endianness::Big,
+            reverse: false,
+        };
+        let packed = enc.process_test(b"1.5".to_vec());
+        assert_eq!(packed, b"\x3f\xf8\x00\x00\x00\x00\x00\x00");
+        assert_eq!(dec.process_test(packed), b"1.5");
+        assert_eq!(
+            dec.process_test(enc.process_test(b"NaN".to_vec())),
+            b"NaN (0x7ff8000000000000)"
+        );
+    }
+}
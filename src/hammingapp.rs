@@ -0,0 +1,106 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+use std::fs;
+
+/// Bit-level Hamming distance between two buffers, over the length of the
+/// shortest one.
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> u64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x ^ y).count_ones() as u64)
+        .sum()
+}
+
+pub struct HammingApplet {
+    with: Vec<u8>,
+    truncate: bool,
+}
+
+impl Applet for HammingApplet {
+    fn command(&self) -> &'static str {
+        "hamming"
+    }
+    fn description(&self) -> &'static str {
+        "bit-level Hamming distance between input and a file"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-w --with <FILE> "file to compare the input with").required(true))
+            .arg(arg!(-t --truncate "compare up to the shortest length instead of failing"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            with: vec![],
+            truncate: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let path = args.get_one::<String>("with").unwrap();
+        let with = fs::read(path).with_context(|| format!("Could not read file {}", path))?;
+        Ok(Box::new(Self {
+            with,
+            truncate: args.get_flag("truncate"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if val.len() != self.with.len() && !self.truncate {
+            bail!(
+                "Lengths differ: input is {} bytes, file is {} bytes (use --truncate)",
+                val.len(),
+                self.with.len()
+            );
+        }
+        Ok(format!("{}\n", hamming_distance(&val, &self.with)).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_cli() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        tmp.write_all(b"wokka wokka!!!").unwrap();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "hamming",
+                "-w",
+                tmp.path().to_str().unwrap(),
+                "this is a test",
+            ])
+            .assert()
+            .stdout("37\n")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hamming", "-w", tmp.path().to_str().unwrap(), "short"])
+            .assert()
+            .stderr(predicates::str::contains("Lengths differ"))
+            .failure();
+    }
+
+    #[test]
+    fn test_hamming() {
+        assert_eq!(hamming_distance(b"this is a test", b"wokka wokka!!!"), 37);
+        assert_eq!(hamming_distance(b"\x00\xff", b"\xff\xff"), 8);
+        assert_eq!(hamming_distance(b"", b""), 0);
+
+        let hamming = HammingApplet {
+            with: b"\x0f\x00\xff".to_vec(),
+            truncate: true,
+        };
+        assert_eq!(hamming.process_test(b"\x00\x01".to_vec()), b"5\n");
+    }
+}
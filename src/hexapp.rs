@@ -1,9 +1,33 @@
 use crate::applet::Applet;
-use crate::applet::SliceExt;
+use crate::applet::{parse_byte_set, SliceExt};
 use anyhow::{Context, Result};
 use clap::{arg, Command};
+use std::io::{Read, Write};
 
-pub struct HexApplet {}
+/* Size of the chunks read when streaming */
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
+pub struct HexApplet {
+    uppercase: bool,
+    swap_nibbles: bool,
+}
+
+impl HexApplet {
+    fn encode(&self, val: &[u8]) -> String {
+        let swapped: Vec<u8>;
+        let val = if self.swap_nibbles {
+            swapped = val.iter().map(|b| b.rotate_left(4)).collect();
+            &swapped
+        } else {
+            val
+        };
+        if self.uppercase {
+            hex::encode_upper(val)
+        } else {
+            hex::encode(val)
+        }
+    }
+}
 
 impl Applet for HexApplet {
     fn command(&self) -> &'static str {
@@ -13,27 +37,79 @@ impl Applet for HexApplet {
         "hex encode"
     }
 
-    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
-        Ok(Box::new(Self {}))
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-u --uppercase "use uppercase hex digits"))
+            .arg(arg!(-n --"swap-nibbles" "output the low nibble of each byte first (0x12 => \"21\")"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            uppercase: args.get_flag("uppercase"),
+            swap_nibbles: args.get_flag("swap-nibbles"),
+        }))
+    }
+
+    fn explain(&self) -> String {
+        format!(
+            "hex: encode each byte as two {} hex digits{}.",
+            if self.uppercase {
+                "uppercase"
+            } else {
+                "lowercase"
+            },
+            if self.swap_nibbles {
+                ", low nibble first"
+            } else {
+                ""
+            }
+        )
     }
 
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
-        Ok(hex::encode(val).as_bytes().to_vec())
+        Ok(self.encode(&val).into_bytes())
+    }
+
+    fn process_stream(&self, input: &mut dyn Read, output: &mut dyn Write) -> Result<()> {
+        let mut buf = vec![0; STREAM_CHUNK_LEN];
+        loop {
+            let len = match input.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(len) => len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e).with_context(|| "Reading input failed"),
+            };
+            output.write_all(self.encode(&buf[..len]).as_bytes())?;
+        }
+    }
+
+    fn returns_text(&self) -> bool {
+        true
     }
 
     fn new() -> Box<dyn Applet> {
-        Box::new(Self {})
+        Box::new(Self {
+            uppercase: false,
+            swap_nibbles: false,
+        })
     }
 }
 
 pub struct UnHexApplet {
     hexonly: bool,
     strict: bool,
+    trim_bytes: Option<Vec<u8>>,
 }
 
 impl UnHexApplet {
     fn hex_decode_hexonly(&self, val: Vec<u8>) -> Result<Vec<u8>> {
-        let mut trimmed: Vec<u8> = val.trim().into();
+        /* --trim-bytes replaces the default whitespace trimming */
+        let mut trimmed: Vec<u8> = match self.trim_bytes {
+            Some(_) => val,
+            None => val.trim().into(),
+        };
         let res = hex::decode(&trimmed);
         if self.strict {
             return res.with_context(|| "Invalid hex input");
@@ -106,6 +182,7 @@ impl Applet for UnHexApplet {
         Box::new(Self {
             hexonly: false,
             strict: false,
+            trim_bytes: None,
         })
     }
 
@@ -113,18 +190,28 @@ impl Applet for UnHexApplet {
         Command::new(self.command()).about(self.description())
              .arg(arg!(-o --"hex-only"  "expect only hex data, stop at first non-hex byte (but copy the rest, except spaces)"))
              .arg(arg!(-s --strict  "strict decoding, error on invalid data"))
+             .arg(arg!(--"trim-bytes" <HEXLIST> "bytes to trim from the input before decoding, in hex (ex: 22 for quotes)"))
              .arg(arg!([value]  "input value, reads from stdin if not present"))
              .after_help("By default, decode all hex data in the input, regardless of garbage in-between.")
     }
 
     fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let trim_bytes = match args.get_one::<String>("trim-bytes") {
+            Some(list) => Some(parse_byte_set(list)?),
+            None => None,
+        };
         Ok(Box::new(Self {
             hexonly: args.get_flag("hex-only") || args.get_flag("strict"),
             strict: args.get_flag("strict"),
+            trim_bytes,
         }))
     }
 
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let val = match &self.trim_bytes {
+            Some(set) => val.trim_bytes(set).to_vec(),
+            None => val,
+        };
         if self.hexonly {
             self.hex_decode_hexonly(val)
         } else {
@@ -136,12 +223,13 @@ impl Applet for UnHexApplet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::applet::{build_applet, check_stream};
 
     #[test]
     fn test_hex_cli_arg() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["hex", "aAé!"])
+            .args(["hex", "aAé!"])
             .assert()
             .stdout("6141c3a921")
             .success();
@@ -151,18 +239,40 @@ mod tests {
     fn test_hex_cli_stdin() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["hex"])
+            .args(["hex"])
             .write_stdin("aAé!\n")
             .assert()
             .stdout("6141c3a9210a")
             .success();
     }
 
+    #[test]
+    fn test_hex_cli_fold() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--fold", "16", "abcdefghij"])
+            .assert()
+            .stdout("6162636465666768\n696a")
+            .success();
+    }
+
+    #[test]
+    fn test_hex_stream() {
+        let hex = HexApplet {
+            uppercase: false,
+            swap_nibbles: false,
+        };
+        let data: Vec<u8> = (0..STREAM_CHUNK_LEN * 3 + 17)
+            .map(|i| (i * 7) as u8)
+            .collect();
+        check_stream(&hex, &data);
+    }
+
     #[test]
     fn test_unhex_cli_arg() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["unhex", "6141210a00ff"])
+            .args(["unhex", "6141210a00ff"])
             .assert()
             .stdout(&b"aA!\n\x00\xff"[..])
             .success();
@@ -172,7 +282,7 @@ mod tests {
     fn test_unhex_cli_stdin() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["unhex"])
+            .args(["unhex"])
             .write_stdin("41ff\n00FF")
             .assert()
             .stdout(&[0x41, 0xFF, 0x0A, 0x00, 0xFF][..])
@@ -183,7 +293,7 @@ mod tests {
     fn test_unhex_cli_stdin_hexonly() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["unhex", "-o"])
+            .args(["unhex", "-o"])
             .write_stdin("41ff\n00FF")
             .assert()
             .stdout(&b"A\xFF\n00FF"[..])
@@ -194,7 +304,7 @@ mod tests {
     fn test_unhex_cli_stdin_strict() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["unhex", "-s"])
+            .args(["unhex", "-s"])
             .write_stdin("41l")
             .assert()
             .stdout(&b""[..])
@@ -202,7 +312,7 @@ mod tests {
             .failure();
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["unhex", "-s"])
+            .args(["unhex", "-s"])
             .write_stdin("41ll")
             .assert()
             .stdout(&b""[..])
@@ -212,18 +322,46 @@ mod tests {
 
     #[test]
     fn test_hex() {
-        let hex = HexApplet {};
+        let hex = HexApplet {
+            uppercase: false,
+            swap_nibbles: false,
+        };
         assert_eq!(
             String::from_utf8(hex.process_test([0, 0xFF].to_vec())).unwrap(),
             "00ff"
         );
     }
 
+    #[test]
+    fn test_hex_swap_nibbles() {
+        let mut hex = HexApplet {
+            uppercase: false,
+            swap_nibbles: true,
+        };
+        assert_eq!(hex.process_test(vec![0x12, 0xab, 0x0f]), b"21baf0");
+        hex.uppercase = true;
+        assert_eq!(hex.process_test(vec![0x12, 0xab, 0x0f]), b"21BAF0");
+
+        let mut streamed = Vec::new();
+        hex.process_stream(&mut &[0x12u8, 0xcd][..], &mut streamed)
+            .unwrap();
+        assert_eq!(streamed, b"21DC");
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--uppercase", "--swap-nibbles"])
+            .write_stdin(vec![0x12, 0xab])
+            .assert()
+            .stdout("21BA")
+            .success();
+    }
+
     #[test]
     fn test_unhex_hexonly() {
         let unhex = UnHexApplet {
             strict: false,
             hexonly: true,
+            trim_bytes: None,
         };
         assert_eq!(
             unhex
@@ -244,6 +382,7 @@ mod tests {
         let unhex = UnHexApplet {
             strict: false,
             hexonly: false,
+            trim_bytes: None,
         };
         assert_eq!(
             unhex.process("test52af ".as_bytes().to_vec()).unwrap(),
@@ -262,4 +401,47 @@ mod tests {
             [0x21, 0x35, 0x20, 0x2a, 0x66]
         );
     }
+
+    #[test]
+    fn test_unhex_trim_bytes() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["unhex", "-s", "--trim-bytes", "22", "\"41ff\""])
+            .assert()
+            .stdout(&b"A\xff"[..])
+            .success();
+
+        let unhex = UnHexApplet {
+            strict: false,
+            hexonly: false,
+            trim_bytes: Some(vec![b'"', b'[', b']']),
+        };
+        assert_eq!(unhex.process_test(b"[\"4142\"]".to_vec()), b"AB");
+        assert_eq!(unhex.process_test(b"\"\"".to_vec()), b"");
+
+        let unhex = UnHexApplet {
+            strict: true,
+            hexonly: true,
+            trim_bytes: Some(vec![b'"', b'\n']),
+        };
+        assert_eq!(unhex.process_test(b"\"4142\"\n".to_vec()), b"AB");
+        assert!(unhex.process(b"4142 ".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_explain() {
+        let explain = |args: &[&str]| {
+            build_applet(HexApplet::new().as_ref(), args)
+                .unwrap()
+                .explain()
+        };
+        assert_eq!(
+            explain(&[]),
+            "hex: encode each byte as two lowercase hex digits."
+        );
+        assert_eq!(
+            explain(&["-u", "-n"]),
+            "hex: encode each byte as two uppercase hex digits, low nibble first."
+        );
+    }
 }
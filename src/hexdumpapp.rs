@@ -0,0 +1,467 @@
+use crate::applet::Applet;
+use crate::bgrepapp::{build_pattern, hex_pattern};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+use regex::bytes::Regex;
+
+const LINE_LEN: usize = 16;
+/* Maximum length of data repeated by a '*' line, against bogus offsets */
+const MAX_REPEAT_LEN: usize = 1 << 30;
+const ANSI_HL: &str = "\x1b[7m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DumpFormat {
+    /* "00000000: 4142 4344  ABCD" */
+    Xxd,
+    /* "00000000  41 42 43 44  |ABCD|" */
+    HexdumpC,
+    /* "0000000 041101 042103" or "0000000 41 42 43 44", no ASCII column */
+    Od,
+}
+
+/* Split a dump line into its offset and data parts */
+fn split_offset(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    let end = line
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(line.len());
+    if end == 0 {
+        return None;
+    }
+    let rest = &line[end..];
+    Some((&line[..end], rest.strip_prefix(':').unwrap_or(rest)))
+}
+
+/* Decode a data token, multi-bytes words are little endian (od, hexdump) */
+fn decode_token(token: &str, res: &mut Vec<u8>) -> Result<()> {
+    let (radix, size) = match token.len() {
+        2 => (16, 1),
+        4 => (16, 2),
+        6 => (8, 2),
+        8 => (16, 4),
+        _ => bail!("Unsupported hexdump token \"{}\"", token),
+    };
+    let val = u32::from_str_radix(token, radix)
+        .with_context(|| format!("Invalid hexdump token \"{}\"", token))?;
+    res.extend_from_slice(&val.to_le_bytes()[..size]);
+    Ok(())
+}
+
+/* Radix of od offsets: octal by default, hex with -A x. The offsets are
+ * checked against the line lengths: the next line directly follows, lines
+ * after '*' come after at least one repeat of the line. Defaults to octal when
+ * both radixes are consistent */
+fn od_radix(lines: &[&str]) -> Result<u32> {
+    /* offset as octal and hex, length of the previous line */
+    let mut prev: Option<(usize, usize, usize)> = None;
+    let mut repeat = false;
+    for line in lines {
+        if line.trim() == "*" {
+            repeat = true;
+            continue;
+        }
+        let (offset, data) = split_offset(line)
+            .with_context(|| format!("Hexdump line without offset: \"{}\"", line))?;
+        let (octal, hex) = match usize::from_str_radix(offset, 8) {
+            Ok(o) => (o, usize::from_str_radix(offset, 16)?),
+            Err(_) => return Ok(16),
+        };
+        if let Some((prev_octal, prev_hex, len)) = prev {
+            let valid = |prev: usize, cur: usize| match cur.checked_sub(prev) {
+                Some(d) if repeat => len > 0 && d >= 2 * len,
+                Some(d) => d == len,
+                None => false,
+            };
+            match (valid(prev_octal, octal), valid(prev_hex, hex)) {
+                (true, false) => return Ok(8),
+                (false, true) => return Ok(16),
+                _ => (),
+            }
+        }
+        let mut line_bytes = Vec::new();
+        for token in data.split_whitespace() {
+            decode_token(token, &mut line_bytes)?;
+        }
+        prev = Some((octal, hex, line_bytes.len()));
+        repeat = false;
+    }
+    Ok(8)
+}
+
+/// Parse the output of `xxd`, `hexdump -C` or `od` back to raw bytes.
+/// The format is detected from the line structure, `*` lines (repeated
+/// content) are supported.
+pub fn parse_hexdump(dump: &[u8]) -> Result<Vec<u8>> {
+    let dump = String::from_utf8_lossy(dump);
+    let lines: Vec<&str> = dump.lines().filter(|l| !l.trim().is_empty()).collect();
+    let first = lines.first().context("Empty hexdump")?;
+    let (first_offset, _) = split_offset(first).context("Hexdump line without offset")?;
+    let format = if first.trim_start()[first_offset.len()..].starts_with(':') {
+        DumpFormat::Xxd
+    } else if first.contains('|') {
+        DumpFormat::HexdumpC
+    } else {
+        DumpFormat::Od
+    };
+
+    let mut res: Vec<u8> = Vec::new();
+    let mut prev_line: Vec<u8> = Vec::new();
+    let mut repeat = false;
+    let radix = match format {
+        DumpFormat::Od => od_radix(&lines)?,
+        _ => 16,
+    };
+    for line in lines.iter() {
+        if line.trim() == "*" {
+            repeat = true;
+            continue;
+        }
+        let (offset, data) = split_offset(line)
+            .with_context(|| format!("Hexdump line without offset: \"{}\"", line))?;
+        let offset = usize::from_str_radix(offset, radix)
+            .with_context(|| format!("Invalid offset in \"{}\"", line))?;
+        if repeat {
+            if offset.saturating_sub(res.len()) > MAX_REPEAT_LEN {
+                bail!("Offset too large in \"{}\"", line);
+            }
+            while !prev_line.is_empty() && res.len() < offset {
+                res.extend_from_slice(&prev_line);
+            }
+            res.truncate(offset);
+            repeat = false;
+        }
+        let data = match format {
+            DumpFormat::Xxd => data.trim_start().split("  ").next().unwrap_or(""),
+            DumpFormat::HexdumpC => data.split('|').next().unwrap_or(""),
+            DumpFormat::Od => data,
+        };
+        let mut line_bytes = Vec::new();
+        if format == DumpFormat::Xxd {
+            line_bytes = hex::decode(data.replace(' ', ""))
+                .with_context(|| format!("Invalid hex in \"{}\"", line))?;
+        } else {
+            for token in data.split_whitespace() {
+                decode_token(token, &mut line_bytes)?;
+            }
+        }
+        if line_bytes.is_empty() {
+            // final line, with the total length: remove od/hexdump padding
+            res.truncate(offset);
+        }
+        res.extend_from_slice(&line_bytes);
+        prev_line = line_bytes;
+    }
+    Ok(res)
+}
+
+pub struct HexdumpApplet {
+    highlight: Option<Regex>,
+    color: bool,
+}
+
+impl HexdumpApplet {
+    /* Returns a map of the bytes which are part of a match */
+    fn highlighted(&self, val: &[u8]) -> Vec<bool> {
+        let mut res = vec![false; val.len()];
+        if let Some(regex) = &self.highlight {
+            for m in regex.find_iter(val) {
+                res[m.start()..m.end()].fill(true);
+            }
+        }
+        res
+    }
+
+    /* Explicit markers: '[' before the first highlighted byte, ']' after the last one.
+     * Markers use the separator columns, so the layout is the same as without
+     * highlighting. Highlights spanning several lines are closed at the end of
+     * each line and reopened at the start of the next one. */
+    fn dump_line_brackets(line: &[u8], hl: &[bool], res: &mut String) {
+        for j in 0..LINE_LEN {
+            if j == LINE_LEN / 2 {
+                res.push(' ');
+            }
+            let cur = j < line.len() && hl[j];
+            let prev = j > 0 && j <= line.len() && hl[j - 1];
+            res.push(match (prev, cur) {
+                (false, true) => '[',
+                (true, false) => ']',
+                _ => ' ',
+            });
+            if j < line.len() {
+                res.push_str(&format!("{:02x}", line[j]));
+            } else {
+                res.push_str("  ");
+            }
+        }
+        res.push(if line.len() == LINE_LEN && hl[LINE_LEN - 1] {
+            ']'
+        } else {
+            ' '
+        });
+    }
+
+    fn dump_line_color(line: &[u8], hl: &[bool], res: &mut String) {
+        for j in 0..LINE_LEN {
+            if j == LINE_LEN / 2 {
+                res.push(' ');
+            }
+            res.push(' ');
+            if j < line.len() {
+                if hl[j] {
+                    res.push_str(&format!("{}{:02x}{}", ANSI_HL, line[j], ANSI_RESET));
+                } else {
+                    res.push_str(&format!("{:02x}", line[j]));
+                }
+            } else {
+                res.push_str("  ");
+            }
+        }
+        res.push(' ');
+    }
+}
+
+impl Applet for HexdumpApplet {
+    fn command(&self) -> &'static str {
+        "hexdump"
+    }
+    fn description(&self) -> &'static str {
+        "hexdump, with optional highlighting"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-H --highlight <HEX> "highlight occurrences of the given hex pattern"))
+            .arg(arg!(-c --color "use ANSI colors instead of [] markers for highlighting"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            highlight: None,
+            color: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let highlight = match args.get_one::<String>("highlight") {
+            Some(h) => Some(build_pattern(&hex_pattern(&h.replace(' ', ""))?)?),
+            None => None,
+        };
+        Ok(Box::new(Self {
+            highlight,
+            color: args.get_flag("color"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let hl = self.highlighted(&val);
+        let mut res = String::new();
+        for (i, line) in val.chunks(LINE_LEN).enumerate() {
+            let off = i * LINE_LEN;
+            let line_hl = &hl[off..off + line.len()];
+            res.push_str(&format!("{:08x} ", off));
+            if self.color {
+                Self::dump_line_color(line, line_hl, &mut res);
+            } else {
+                Self::dump_line_brackets(line, line_hl, &mut res);
+            }
+            res.push_str(" |");
+            for (c, h) in line.iter().zip(line_hl) {
+                let c = if c.is_ascii_graphic() || *c == b' ' {
+                    *c as char
+                } else {
+                    '.'
+                };
+                if self.color && *h {
+                    res.push_str(&format!("{}{}{}", ANSI_HL, c, ANSI_RESET));
+                } else {
+                    res.push(c);
+                }
+            }
+            res.push_str("|\n");
+        }
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hexdump", "-H", "4243", "ABCD"])
+            .assert()
+            .stdout("00000000  41[42 43]44                                       |ABCD|\n")
+            .success();
+    }
+
+    #[test]
+    fn test_dump() {
+        let hd = HexdumpApplet {
+            highlight: None,
+            color: false,
+        };
+        assert_eq!(
+            String::from_utf8(hd.process_test(b"0123456789abcdef\x00\xff\n".to_vec())).unwrap(),
+            "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             00000010  00 ff 0a                                          |...|\n"
+        );
+        assert_eq!(hd.process_test(vec![]), b"");
+    }
+
+    #[test]
+    fn test_highlight() {
+        let hd = HexdumpApplet {
+            highlight: Some(build_pattern(&hex_pattern("0f1011").unwrap()).unwrap()),
+            color: false,
+        };
+        let data: Vec<u8> = (0..0x20).collect();
+        // match starting at offset 0xf, spanning two lines
+        assert_eq!(
+            String::from_utf8(hd.process_test(data)).unwrap(),
+            "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e[0f] |................|\n\
+             00000010 [10 11]12 13 14 15 16 17  18 19 1a 1b 1c 1d 1e 1f  |................|\n"
+        );
+
+        let hd = HexdumpApplet {
+            highlight: Some(build_pattern(&hex_pattern("41").unwrap()).unwrap()),
+            color: false,
+        };
+        // adjacent matches are merged, match at the end of a short line
+        assert_eq!(
+            String::from_utf8(hd.process_test(b"AAbA".to_vec())).unwrap(),
+            "00000000 [41 41]62[41]                                      |AAbA|\n"
+        );
+
+        let hd = HexdumpApplet {
+            highlight: Some(build_pattern(&hex_pattern("62").unwrap()).unwrap()),
+            color: true,
+        };
+        assert_eq!(
+            String::from_utf8(hd.process_test(b"ab".to_vec())).unwrap(),
+            "00000000  61 \x1b[7m62\x1b[0m                                             |a\x1b[7mb\x1b[0m|\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_xxd() {
+        let dump = "00000000: 6865 6c6c 6f20 776f 726c 642c 2074 6869  hello world, thi\n\
+                    00000010: 7320 6461 7461 00  s data.\n";
+        assert_eq!(
+            parse_hexdump(dump.as_bytes()).unwrap(),
+            b"hello world, this data\x00"
+        );
+        // xxd -g1, ASCII column starting with spaces
+        let dump = "00000000: 20 20 41 42    ..AB\n";
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), b"  AB");
+    }
+
+    #[test]
+    fn test_parse_hexdump_c() {
+        let data: Vec<u8> = b"0123456789abcdef"
+            .iter()
+            .copied()
+            .chain([0u8; 48])
+            .chain(*b"end")
+            .collect();
+        let dump = "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+                    00000010  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|\n\
+                    *\n\
+                    00000040  65 6e 64                                          |end|\n\
+                    00000043\n";
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), data);
+
+        // round trip with the hexdump applet
+        let hd = HexdumpApplet {
+            highlight: None,
+            color: false,
+        };
+        assert_eq!(parse_hexdump(&hd.process_test(data.clone())).unwrap(), data);
+    }
+
+    #[test]
+    fn test_parse_od() {
+        // od, octal words, odd length
+        let dump = "0000000 062550 066154 020157 067567 066162 026144 072040 064550\n\
+                    0000020 000000 000000 000000 000000 000000 000000 000000 000000\n\
+                    *\n\
+                    0000060 000144\n\
+                    0000061\n";
+        let mut expected = b"hello world, thi".to_vec();
+        expected.extend_from_slice(&[0; 32]);
+        expected.push(b'd');
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), expected);
+
+        // od -t x1
+        let dump = "0000000 68 65 6c 6c 6f\n0000005\n";
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), b"hello");
+
+        // od, all zeros first line: the offset radix cannot be checked on the
+        // second line. head -c 64 /dev/zero | od
+        let dump = "0000000 000000 000000 000000 000000 000000 000000 000000 000000\n\
+                    *\n\
+                    0000100\n";
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), [0; 64]);
+        let dump = "0000000 000000 000000 000000 000000 000000 000000 000000 000000\n\
+                    *\n\
+                    0000100 062550 066154\n\
+                    0000104\n";
+        let mut expected = vec![0; 64];
+        expected.extend_from_slice(b"hell");
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), expected);
+
+        // od -A x
+        let dump = "000000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+                    *\n\
+                    000020 41 42\n\
+                    000022\n";
+        let mut expected = vec![0; 32];
+        expected.extend_from_slice(b"AB");
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), expected);
+        let dump = "000000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\n\
+                    *\n\
+                    0000a0\n";
+        assert_eq!(parse_hexdump(dump.as_bytes()).unwrap(), [0; 0xa0]);
+
+        // bogus offset after a repeat
+        let dump = "0000000 000000\n*\n7777777777777777\n";
+        assert!(parse_hexdump(dump.as_bytes()).is_err());
+
+        assert!(parse_hexdump(b"").is_err());
+        assert!(parse_hexdump(b"0000000 zz\n").is_err());
+    }
+
+    #[test]
+    fn test_from_hexdump_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--from-hexdump"])
+            .write_stdin("00000000: 4142 0a                   AB.\n")
+            .assert()
+            .stdout("41420a")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--from-hexdump"])
+            .write_stdin("00000000  41 42 3a  |AB:|\n00000003\n")
+            .assert()
+            .stdout("41423a")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--from-hexdump"])
+            .write_stdin(
+                "0000000 000000 000000 000000 000000 000000 000000 000000 000000\n*\n0000100\n",
+            )
+            .assert()
+            .stdout("00".repeat(64))
+            .success();
+    }
+}
@@ -0,0 +1,143 @@
+use clap::{arg, value_parser, Command};
+
+/// Formatting of the JSON-like output of applets: indented (the default)
+/// or compact, on a single line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JsonFormat {
+    /* None for compact output */
+    indent: Option<usize>,
+}
+
+impl Default for JsonFormat {
+    fn default() -> Self {
+        Self { indent: Some(2) }
+    }
+}
+
+impl JsonFormat {
+    /// Add the `--indent` and `--compact` options to an applet command.
+    pub fn add_args(cmd: Command) -> Command {
+        cmd.arg(
+            arg!(--indent <N> "indentation width of JSON output")
+                .value_parser(value_parser!(u8).range(0..=16))
+                .default_value("2"),
+        )
+        .arg(arg!(--compact "output JSON on a single line").conflicts_with("indent"))
+    }
+
+    pub fn from_args(args: &clap::ArgMatches) -> Self {
+        if args.get_flag("compact") {
+            Self { indent: None }
+        } else {
+            Self {
+                indent: Some(*args.get_one::<u8>("indent").unwrap() as usize),
+            }
+        }
+    }
+
+    /// Start a new line at the given nesting level, nothing in compact mode.
+    pub fn newline(&self, level: usize, res: &mut String) {
+        if let Some(indent) = self.indent {
+            res.push('\n');
+            res.push_str(&" ".repeat(indent * level));
+        }
+    }
+
+    /// Separator between object keys and values.
+    pub fn key_sep(&self) -> &'static str {
+        match self.indent {
+            Some(_) => ": ",
+            None => ":",
+        }
+    }
+
+    /// Render a sequence of items between `delims`, one per line when
+    /// indenting. `render_item` is called for each item, at `level + 1`.
+    pub fn render_seq<T>(
+        &self,
+        delims: (char, char),
+        items: &[T],
+        level: usize,
+        res: &mut String,
+        mut render_item: impl FnMut(&T, &mut String),
+    ) {
+        res.push(delims.0);
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                res.push(',');
+            }
+            self.newline(level + 1, res);
+            render_item(item, res);
+        }
+        if !items.is_empty() {
+            self.newline(level, res);
+        }
+        res.push(delims.1);
+    }
+
+    /// Render a JSON value.
+    pub fn render(&self, val: &serde_json::Value, level: usize, res: &mut String) {
+        match val {
+            serde_json::Value::Array(a) => self.render_seq(('[', ']'), a, level, res, |v, res| {
+                self.render(v, level + 1, res)
+            }),
+            serde_json::Value::Object(o) => {
+                let items: Vec<_> = o.iter().collect();
+                self.render_seq(('{', '}'), &items, level, res, |(k, v), res| {
+                    escape_json_str(k, res);
+                    res.push_str(self.key_sep());
+                    self.render(v, level + 1, res);
+                })
+            }
+            serde_json::Value::String(s) => escape_json_str(s, res),
+            v => res.push_str(&v.to_string()),
+        }
+    }
+}
+
+pub fn escape_json_str(s: &str, res: &mut String) {
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let val = serde_json::json!({"a": [1, "b\n"], "c": {}, "d": {"e": null}});
+        let render = |fmt: JsonFormat| {
+            let mut res = String::new();
+            fmt.render(&val, 0, &mut res);
+            res
+        };
+        assert_eq!(
+            render(JsonFormat { indent: None }),
+            r#"{"a":[1,"b\n"],"c":{},"d":{"e":null}}"#
+        );
+        assert_eq!(
+            render(JsonFormat::default()),
+            "{\n  \"a\": [\n    1,\n    \"b\\n\"\n  ],\n  \"c\": {},\n  \"d\": {\n    \"e\": null\n  }\n}"
+        );
+        assert_eq!(
+            render(JsonFormat { indent: Some(4) }),
+            "{\n    \"a\": [\n        1,\n        \"b\\n\"\n    ],\n    \"c\": {},\n    \"d\": {\n        \"e\": null\n    }\n}"
+        );
+        // compact output is the same JSON
+        let compact: serde_json::Value =
+            serde_json::from_str(&render(JsonFormat { indent: None })).unwrap();
+        assert_eq!(compact, val);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::applet::Applet;
+use anyhow::{Context, Result};
+use clap::{arg, Command};
+use std::collections::HashMap;
+use std::fs;
+
+/* Suffix automaton state. first_end is the end position (inclusive) of the
+ * first occurrence of the strings recognized by the state */
+struct State {
+    len: usize,
+    link: Option<usize>,
+    next: HashMap<u8, usize>,
+    first_end: usize,
+}
+
+/* Suffix automaton, built in linear time, recognizes all substrings of the
+ * given buffer */
+struct SuffixAutomaton {
+    states: Vec<State>,
+}
+
+impl SuffixAutomaton {
+    fn new(data: &[u8]) -> Self {
+        let mut states = Vec::with_capacity(data.len() * 2 + 1);
+        states.push(State {
+            len: 0,
+            link: None,
+            next: HashMap::new(),
+            first_end: 0,
+        });
+        let mut last = 0;
+        for (i, c) in data.iter().enumerate() {
+            let cur = states.len();
+            states.push(State {
+                len: states[last].len + 1,
+                link: None,
+                next: HashMap::new(),
+                first_end: i,
+            });
+            let mut p = Some(last);
+            while let Some(pp) = p {
+                if states[pp].next.contains_key(c) {
+                    break;
+                }
+                states[pp].next.insert(*c, cur);
+                p = states[pp].link;
+            }
+            match p {
+                None => states[cur].link = Some(0),
+                Some(p) => {
+                    let q = states[p].next[c];
+                    if states[p].len + 1 == states[q].len {
+                        states[cur].link = Some(q);
+                    } else {
+                        let clone = states.len();
+                        states.push(State {
+                            len: states[p].len + 1,
+                            link: states[q].link,
+                            next: states[q].next.clone(),
+                            first_end: states[q].first_end,
+                        });
+                        let mut p = Some(p);
+                        while let Some(pp) = p {
+                            if states[pp].next.get(c) != Some(&q) {
+                                break;
+                            }
+                            states[pp].next.insert(*c, clone);
+                            p = states[pp].link;
+                        }
+                        states[q].link = Some(clone);
+                        states[cur].link = Some(clone);
+                    }
+                }
+            }
+            last = cur;
+        }
+        Self { states }
+    }
+
+    /* Returns (length, offset in other, offset in the automaton data) of the
+     * first longest common substring */
+    fn longest_common(&self, other: &[u8]) -> Option<(usize, usize, usize)> {
+        let mut best: Option<(usize, usize, usize)> = None;
+        let mut state = 0;
+        let mut len = 0;
+        for (i, c) in other.iter().enumerate() {
+            while state != 0 && !self.states[state].next.contains_key(c) {
+                state = self.states[state].link.unwrap();
+                len = self.states[state].len;
+            }
+            if let Some(next) = self.states[state].next.get(c) {
+                state = *next;
+                len += 1;
+            }
+            if len > 0 && best.map_or(true, |(l, _, _)| len > l) {
+                best = Some((len, i + 1 - len, self.states[state].first_end + 1 - len));
+            }
+        }
+        best
+    }
+}
+
+pub struct LcsApplet {
+    with: Vec<u8>,
+}
+
+impl Applet for LcsApplet {
+    fn command(&self) -> &'static str {
+        "lcs"
+    }
+    fn description(&self) -> &'static str {
+        "longest common substring between input and a file"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-w --with <FILE> "file to compare the input with").required(true))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Outputs the length of the longest common byte substring, and its \
+                 offset in the input and in the file. The first one is reported if \
+                 several have the same length.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { with: vec![] })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let path = args.get_one::<String>("with").unwrap();
+        let with = fs::read(path).with_context(|| format!("Could not read file {}", path))?;
+        Ok(Box::new(Self { with }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let sam = SuffixAutomaton::new(&self.with);
+        let res = match sam.longest_common(&val) {
+            Some((len, input_off, with_off)) => format!(
+                "length: {}\ninput offset: 0x{:x}\nwith offset: 0x{:x}\n",
+                len, input_off, with_off
+            ),
+            None => "length: 0\n".to_string(),
+        };
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_cli() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        tmp.write_all(b"\x00\x01\x02shared run\xff\xfe").unwrap();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["lcs", "-w", tmp.path().to_str().unwrap()])
+            .write_stdin(&b"AAAA\x01\x02shared ruX"[..])
+            .assert()
+            .stdout("length: 11\ninput offset: 0x4\nwith offset: 0x1\n")
+            .success();
+    }
+
+    #[test]
+    fn test_lcs() {
+        let lcs = LcsApplet {
+            with: b"xabcdefgy".to_vec(),
+        };
+        assert_eq!(
+            String::from_utf8(lcs.process_test(b"__cdefg_abc".to_vec())).unwrap(),
+            "length: 5\ninput offset: 0x2\nwith offset: 0x3\n"
+        );
+        assert_eq!(lcs.process_test(b"zzz".to_vec()), b"length: 0\n");
+        assert_eq!(lcs.process_test(vec![]), b"length: 0\n");
+
+        // repetitions exercise the state cloning
+        let sam = SuffixAutomaton::new(b"abababbab");
+        assert_eq!(sam.longest_common(b"bbabab"), Some((5, 1, 1)));
+        assert_eq!(sam.longest_common(b"abababbab"), Some((9, 0, 0)));
+    }
+
+    #[test]
+    fn test_against_naive() {
+        fn naive(a: &[u8], b: &[u8]) -> usize {
+            let mut best = 0;
+            for i in 0..a.len() {
+                for j in 0..b.len() {
+                    let l = a[i..]
+                        .iter()
+                        .zip(&b[j..])
+                        .take_while(|(x, y)| x == y)
+                        .count();
+                    best = best.max(l);
+                }
+            }
+            best
+        }
+        // simple deterministic pseudo random data over a small alphabet
+        let mut seed: u32 = 1;
+        let mut gen = |n: usize| -> Vec<u8> {
+            (0..n)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    b"abc"[(seed >> 16) as usize % 3]
+                })
+                .collect()
+        };
+        for _ in 0..20 {
+            let a = gen(40);
+            let b = gen(30);
+            let (len, off_b, off_a) = SuffixAutomaton::new(&a).longest_common(&b).unwrap();
+            assert_eq!(len, naive(&a, &b));
+            assert_eq!(a[off_a..off_a + len], b[off_b..off_b + len]);
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use anyhow::{anyhow, Context, Result};
 
+use std::ffi::{OsStr, OsString};
 use std::io::{self, IsTerminal};
 use std::io::{Read, Write};
 use std::path::Path;
 extern crate base64;
 extern crate clap;
-use clap::Command;
+use clap::{arg, value_parser, Command};
 extern crate crc;
 extern crate hex;
 extern crate percent_encoding;
@@ -13,6 +14,10 @@ extern crate percent_encoding;
 mod applet;
 use applet::Applet;
 
+mod json;
+mod output;
+use output::{fd_writer, FoldWriter, RateWriter};
+
 mod hexapp;
 use hexapp::HexApplet;
 use hexapp::UnHexApplet;
@@ -37,7 +42,7 @@ mod sliceapp;
 use sliceapp::SliceApplet;
 
 mod timeapp;
-use timeapp::TimeApplet;
+use timeapp::{FiletimeApplet, TimeApplet};
 
 mod patternapp;
 use patternapp::BofPattGenApplet;
@@ -63,6 +68,100 @@ mod escapeapp;
 use escapeapp::EscapeApplet;
 use escapeapp::UnEscapeApplet;
 
+mod stringsapp;
+use stringsapp::StringsApplet;
+
+mod derefapp;
+use derefapp::{DerefApplet, PrefixLenApplet};
+
+mod cborapp;
+use cborapp::CborApplet;
+
+mod checksumapp;
+use checksumapp::ChecksumApplet;
+
+mod punyapp;
+use punyapp::PunyApplet;
+
+mod verifyapp;
+use verifyapp::VerifyApplet;
+
+mod floatapp;
+use floatapp::FloatApplet;
+
+mod hexdumpapp;
+use hexdumpapp::{parse_hexdump, HexdumpApplet};
+
+mod deltaapp;
+use deltaapp::DeltaApplet;
+
+mod emitapp;
+use emitapp::EmitApplet;
+
+mod parselitapp;
+use parselitapp::ParseLitApplet;
+
+mod lcsapp;
+use lcsapp::LcsApplet;
+
+mod fieldapp;
+use fieldapp::FieldApplet;
+
+mod b45app;
+use b45app::B45DecApplet;
+use b45app::B45EncApplet;
+
+mod stripansiapp;
+use stripansiapp::StripAnsiApplet;
+
+mod replaceapp;
+use replaceapp::ReplaceApplet;
+
+mod payloadsapp;
+use payloadsapp::PayloadsApplet;
+
+mod diffapp;
+use diffapp::DiffApplet;
+
+mod hammingapp;
+use hammingapp::HammingApplet;
+
+mod pkcs7app;
+use pkcs7app::Pkcs7Applet;
+
+mod aesapp;
+use aesapp::AesApplet;
+
+mod teaapp;
+use teaapp::TeaApplet;
+
+mod rc4app;
+use rc4app::Rc4Applet;
+
+mod selftestapp;
+use selftestapp::SelfTestApplet;
+
+mod dnsnameapp;
+use dnsnameapp::DnsNameApplet;
+
+mod varintapp;
+use varintapp::VarintApplet;
+
+mod protobufapp;
+use protobufapp::ProtobufApplet;
+
+mod cyberchefapp;
+use cyberchefapp::CyberChefApplet;
+
+mod syncapp;
+use syncapp::SyncApplet;
+
+mod structapp;
+use structapp::StructApplet;
+
+mod carveapp;
+use carveapp::CarveApplet;
+
 // Helper to "register" applets
 macro_rules! applets {
     ($a:ident = $($x:ident),* )  =>
@@ -72,6 +171,71 @@ macro_rules! applets {
         };
 }
 
+/* Options handled by `main` for all applets */
+fn add_global_args(cmd: Command) -> Command {
+    cmd.arg(
+        arg!(--fold <N> "wrap text output every N columns (no final newline is added)")
+            .value_parser(value_parser!(u64).range(1..)),
+    )
+    .arg(arg!(--"from-hexdump" "input is a hexdump (xxd, hexdump -C or od), process the bytes it contains"))
+    .arg(
+        arg!(--rate <"BYTES/s"> "throttle output to at most this number of bytes per second")
+            .visible_alias("limit-rate")
+            .value_parser(value_parser!(u64).range(1..)),
+    )
+    .arg(arg!(--explain "describe what the applet would do with these options, without processing input"))
+    .arg(
+        arg!(--fd <N> "write output to the already open file descriptor N instead of stdout")
+            .value_parser(value_parser!(i32).range(0..)),
+    )
+}
+
+/* Let the input value argument accept any bytes, not only valid UTF-8 */
+fn add_raw_value_arg(app: &dyn Applet, cmd: Command) -> Command {
+    match app.arg_or_stdin() {
+        Some(name) if cmd.get_arguments().any(|a| a.get_id() == name) => {
+            cmd.mut_arg(name, |a| a.value_parser(value_parser!(OsString)))
+        }
+        _ => cmd,
+    }
+}
+
+/* Arguments are arbitrary bytes on Unix, UTF-16 on Windows */
+#[cfg(unix)]
+fn os_str_bytes(s: &OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    s.as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes(s: &OsStr) -> Vec<u8> {
+    s.to_string_lossy().as_bytes().to_vec()
+}
+
+/* Expand "@file" arguments with the whitespace-separated content of "file".
+ * "@@value" is passed as the literal "@value", arguments after "--" are
+ * never expanded. */
+fn expand_response_files<I: Iterator<Item = OsString>>(args: I) -> Result<Vec<OsString>> {
+    let mut res = Vec::new();
+    let mut expand = true;
+    for arg in args {
+        match arg.to_str() {
+            Some("--") if expand => {
+                expand = false;
+                res.push(arg);
+            }
+            Some(a) if expand && a.starts_with("@@") => res.push(OsString::from(&a[1..])),
+            Some(a) if expand && a.len() > 1 && a.starts_with('@') => {
+                let content = std::fs::read_to_string(&a[1..])
+                    .with_context(|| format!("Could not read response file \"{}\"", &a[1..]))?;
+                res.extend(content.split_whitespace().map(OsString::from));
+            }
+            _ => res.push(arg),
+        }
+    }
+    Ok(res)
+}
+
 fn main() -> Result<()> {
     applets!(
         apps = HexApplet,
@@ -91,11 +255,45 @@ fn main() -> Result<()> {
         BgrepApplet,
         FindSoApplet,
         TimeApplet,
+        FiletimeApplet,
         DeflateApplet,
         InflateApplet,
         BaseIntApplet,
         EscapeApplet,
-        UnEscapeApplet
+        UnEscapeApplet,
+        StringsApplet,
+        DerefApplet,
+        CborApplet,
+        ChecksumApplet,
+        PunyApplet,
+        VerifyApplet,
+        FloatApplet,
+        HexdumpApplet,
+        DeltaApplet,
+        EmitApplet,
+        ParseLitApplet,
+        LcsApplet,
+        FieldApplet,
+        B45EncApplet,
+        B45DecApplet,
+        StripAnsiApplet,
+        ReplaceApplet,
+        PrefixLenApplet,
+        PayloadsApplet,
+        DiffApplet,
+        HammingApplet,
+        Pkcs7Applet,
+        AesApplet,
+        TeaApplet,
+        Rc4Applet,
+        SelfTestApplet,
+        DnsNameApplet,
+        VarintApplet,
+        ProtobufApplet,
+        CyberChefApplet,
+        SyncApplet,
+        StructApplet,
+        CarveApplet
     );
 
     // Define a busybox-like multicall binary
@@ -117,6 +315,7 @@ fn main() -> Result<()> {
                 )
                 .author("Raphaël Rigo <devel@syscall.eu>")
                 .about("Rust BlackBag")
+                .after_help("Arguments of the form @file are replaced by the content of file, use @@ for a literal @.")
                 .arg_required_else_help(true)
                 .subcommands([Command::new("list").about("list applets")])
                 .subcommands([
@@ -124,12 +323,21 @@ fn main() -> Result<()> {
                 ])
                 .subcommand_value_name("APPLET")
                 .subcommand_help_heading("APPLETS")
-                .subcommands(apps.iter().map(|app| app.clap_command())),
+                .subcommands(
+                    apps.iter()
+                        .map(|app| add_global_args(add_raw_value_arg(app.as_ref(), app.clap_command()))),
+                ),
         )
-        .subcommands(apps.iter().map(|app| app.clap_command()));
+        .subcommands(
+            apps.iter()
+                .map(|app| add_global_args(add_raw_value_arg(app.as_ref(), app.clap_command()))),
+        );
 
     // Parse args
-    let matches = app.get_matches_mut();
+    let args = expand_response_files(std::env::args_os())?;
+    let matches = app
+        .try_get_matches_from_mut(args)
+        .unwrap_or_else(|e| e.exit());
 
     /* Check if we're called as "rsbkb" */
     let subc = matches.subcommand_name();
@@ -179,49 +387,226 @@ fn main() -> Result<()> {
     // Parse applet args and get actual applet with options
     let selected_app = selected_app.parse_args(sub_matches)?;
 
-    let mut inputval = vec![];
+    let fd = sub_matches.get_one::<i32>("fd");
+    let output = || -> Result<Box<dyn Write>> {
+        Ok(match fd {
+            Some(fd) => Box::new(fd_writer(*fd)?),
+            None => Box::new(io::stdout()),
+        })
+    };
+
+    if sub_matches.get_flag("explain") {
+        writeln!(output()?, "{}", selected_app.explain())?;
+        return Ok(());
+    }
+
+    let mut input: Box<dyn Read> = Box::new(io::stdin());
 
     if let Some(argname) = selected_app.arg_or_stdin() {
         /* Check if the given arg is present, else read from stdin */
-        if !sub_matches.contains_id(argname) {
-            io::stdin()
-                .read_to_end(&mut inputval)
-                .expect("Reading stdin failed");
-        } else {
+        if sub_matches.contains_id(argname) {
             /* Check if the given argument could be a filename, which is probably not
              * what the user wants */
-            let argname_val: &String = sub_matches.get_one::<String>(argname).unwrap();
+            let argname_val: &OsStr = sub_matches.get_raw(argname).unwrap().next().unwrap();
             if Path::new(argname_val).exists() {
                 eprintln!(
                     "'{}' is a file, maybe you want to pass it to stdin instead?",
-                    argname_val
+                    argname_val.to_string_lossy()
                 );
             }
-            inputval = argname_val.as_bytes().to_vec();
+            input = Box::new(io::Cursor::new(os_str_bytes(argname_val)));
         }
+    } else {
+        input = Box::new(io::empty());
     };
 
-    let res = selected_app.process(inputval)?;
+    if sub_matches.get_flag("from-hexdump") {
+        let mut dump = vec![];
+        input.read_to_end(&mut dump).expect("Reading stdin failed");
+        input = Box::new(io::Cursor::new(parse_hexdump(&dump)?));
+    }
 
-    if selected_app.returns_data() {
-        let mut stdout = io::stdout();
-        let write_res = stdout.write_all(&res);
+    if !selected_app.returns_data() {
+        let mut inputval = vec![];
+        input
+            .read_to_end(&mut inputval)
+            .expect("Reading stdin failed");
+        if sub_matches.contains_id("fd") {
+            eprintln!("'{}' does not output data, ignoring --fd", subcommand);
+        }
+        selected_app.process(inputval)?;
+    } else {
+        let mut out = output()?;
+        if let Some(rate) = sub_matches.get_one::<u64>("rate") {
+            out = Box::new(RateWriter::new(out, *rate));
+        }
+        if let Some(width) = sub_matches.get_one::<u64>("fold") {
+            if selected_app.returns_text() {
+                out = Box::new(FoldWriter::new(out, *width as usize));
+            } else {
+                eprintln!("'{}' does not output text, ignoring --fold", subcommand);
+            }
+        }
+        let write_res = selected_app
+            .process_stream(&mut input, &mut out)
+            .and_then(|_| Ok(out.flush()?));
 
         // Ignore broken pipe
-        match write_res {
-            Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
-                return Err(err.into());
-            }
-            Err(_) => {
-                return Ok(());
-            }
-            Ok(_) => (),
-        };
+        if let Err(err) = write_res {
+            return match err.downcast_ref::<io::Error>() {
+                Some(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                _ => Err(err),
+            };
+        }
 
         /* Only add a newline when outputing to a terminal */
-        if std::io::stdout().is_terminal() {
+        if fd.is_none() && std::io::stdout().is_terminal() {
             println!();
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn expand(args: &[&str]) -> Vec<OsString> {
+        expand_response_files(args.iter().map(OsString::from)).unwrap()
+    }
+
+    #[test]
+    fn test_response_file_cli() {
+        let mut tmpfile = tempfile::NamedTempFile::new().unwrap();
+        tmpfile.write_all(b"hex\n  AB\n").unwrap();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .arg(format!("@{}", tmpfile.path().to_str().unwrap()))
+            .assert()
+            .stdout("4142")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "@/nonexistent/file"])
+            .assert()
+            .stderr(predicates::str::contains("Could not read response file"))
+            .failure();
+    }
+
+    #[test]
+    fn test_response_file_escape() {
+        assert_eq!(expand(&["rsbkb", "hex", "@@foo"]), ["rsbkb", "hex", "@foo"]);
+        assert_eq!(expand(&["hex", "--", "@foo"]), ["hex", "--", "@foo"]);
+        assert_eq!(expand(&["hex", "@"]), ["hex", "@"]);
+    }
+
+    #[test]
+    fn test_rate_cli() {
+        let start = std::time::Instant::now();
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--rate", "100", "0123456789"])
+            .assert()
+            .stdout("30313233343536373839")
+            .success();
+        // 20 bytes of output at 100 bytes/s
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fd_cli() {
+        let tmpfile = tempfile::NamedTempFile::new().unwrap();
+        let rsbkb = assert_cmd::cargo::cargo_bin("rsbkb");
+
+        assert_cmd::Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "{} hex --fd 3 AB 3>{}",
+                rsbkb.to_str().unwrap(),
+                tmpfile.path().to_str().unwrap()
+            ))
+            .assert()
+            .stdout("")
+            .success();
+        assert_eq!(std::fs::read(tmpfile.path()).unwrap(), b"4142");
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["hex", "--fd", "42", "AB"])
+            .assert()
+            .stderr(predicates::str::contains("Invalid file descriptor 42"))
+            .failure();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_raw_value_cli() {
+        use std::os::unix::ffi::OsStrExt;
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([OsStr::new("hex"), OsStr::from_bytes(b"\xff\x01A\xc3")])
+            .assert()
+            .stdout("ff0141c3")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                OsStr::new("xor"),
+                OsStr::new("-x"),
+                OsStr::new("ff"),
+                OsStr::from_bytes(b"\xff\xfe"),
+            ])
+            .assert()
+            .stdout(predicates::ord::eq(b"\x00\x01".as_slice()))
+            .success();
+        // busybox-style, through a symlink
+        let dir = tempfile::TempDir::new().unwrap();
+        let link = dir.path().join("hex");
+        std::os::unix::fs::symlink(assert_cmd::cargo::cargo_bin("rsbkb"), &link).unwrap();
+        assert_cmd::Command::new(&link)
+            .arg(OsStr::from_bytes(b"\xff\x41"))
+            .assert()
+            .stdout("ff41")
+            .success();
+    }
+
+    #[test]
+    fn test_explain_cli() {
+        // default explanation, the input is not read
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["crc32", "--explain"])
+            .write_stdin("ignored")
+            .assert()
+            .stdout("crc32: compute CRC-32\n")
+            .success();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_explain_fd_cli() {
+        let tmpfile = tempfile::NamedTempFile::new().unwrap();
+        let rsbkb = assert_cmd::cargo::cargo_bin("rsbkb");
+
+        assert_cmd::Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "{} hex -u --explain --fd 3 3>{}",
+                rsbkb.to_str().unwrap(),
+                tmpfile.path().to_str().unwrap()
+            ))
+            .assert()
+            .stdout("")
+            .success();
+        assert_eq!(
+            std::fs::read(tmpfile.path()).unwrap(),
+            b"hex: encode each byte as two uppercase hex digits.\n"
+        );
+    }
+}
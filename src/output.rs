@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Writer which inserts a newline every `width` columns, like GNU `fold -b`.
+/// Existing newlines reset the column count, no final newline is added.
+pub struct FoldWriter<W: Write> {
+    inner: W,
+    width: usize,
+    col: usize,
+}
+
+impl<W: Write> FoldWriter<W> {
+    pub fn new(inner: W, width: usize) -> Self {
+        Self {
+            inner,
+            width,
+            col: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for FoldWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.col == self.width {
+                self.inner.write_all(b"\n")?;
+                self.col = 0;
+            }
+            let avail = (self.width - self.col).min(rest.len());
+            let chunk = match rest[..avail].iter().position(|c| *c == b'\n') {
+                Some(nl) => {
+                    self.col = 0;
+                    &rest[..=nl]
+                }
+                None => {
+                    self.col += avail;
+                    &rest[..avail]
+                }
+            };
+            self.inner.write_all(chunk)?;
+            rest = &rest[chunk.len()..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writer which throttles output to at most `rate` bytes per second.
+/// Data is written in small chunks, sleeping as needed after each one.
+pub struct RateWriter<W: Write> {
+    inner: W,
+    rate: u64,
+    start: Instant,
+    written: u64,
+}
+
+impl<W: Write> RateWriter<W> {
+    pub fn new(inner: W, rate: u64) -> Self {
+        Self {
+            inner,
+            rate,
+            start: Instant::now(),
+            written: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for RateWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        /* Chunks of a tenth of a second worth of data */
+        let chunk_len = (self.rate / 10).max(1) as usize;
+        for chunk in buf.chunks(chunk_len) {
+            self.inner.write_all(chunk)?;
+            /* Flush so that the throttling is visible downstream */
+            self.inner.flush()?;
+            self.written += chunk.len() as u64;
+            let target = Duration::from_secs_f64(self.written as f64 / self.rate as f64);
+            if let Some(wait) = target.checked_sub(self.start.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Open an already open file descriptor for writing, as given to `--fd`.
+#[cfg(unix)]
+pub fn fd_writer(fd: i32) -> Result<File> {
+    use std::os::unix::io::FromRawFd;
+
+    /* from_raw_fd requires an open fd, check it first */
+    std::fs::metadata(format!("/dev/fd/{}", fd))
+        .with_context(|| format!("Invalid file descriptor {}", fd))?;
+    /* The fd is owned by us from now on and will be closed on exit */
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+pub fn fd_writer(_fd: i32) -> Result<File> {
+    anyhow::bail!("--fd is only supported on Unix platforms")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold(width: usize, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut res = Vec::new();
+        {
+            let mut w = FoldWriter::new(&mut res, width);
+            for c in chunks {
+                w.write_all(c).unwrap();
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold(4, &[b"abcdefghij"]), b"abcd\nefgh\nij");
+        assert_eq!(fold(4, &[b"abcdefgh"]), b"abcd\nefgh");
+        assert_eq!(fold(4, &[b"ab", b"cdef", b"g"]), b"abcd\nefg");
+        assert_eq!(fold(4, &[b"ab\ncdefg"]), b"ab\ncdef\ng");
+        assert_eq!(fold(1, &[b"abc"]), b"a\nb\nc");
+        assert_eq!(fold(4, &[b""]), b"");
+    }
+
+    #[test]
+    fn test_rate() {
+        let mut res = Vec::new();
+        let start = Instant::now();
+        {
+            let mut w = RateWriter::new(&mut res, 200);
+            w.write_all(&[0x41; 30]).unwrap();
+            w.write_all(&[0x42; 20]).unwrap();
+        }
+        // 50 bytes at 200 bytes/s
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        assert_eq!(res, [[0x41; 30].as_slice(), &[0x42; 20]].concat());
+    }
+}
@@ -0,0 +1,400 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+/* Number suffixes accepted after integers, compared in lowercase.
+ * C: u, l, ul, ll, ... Rust: u8, i32, ... */
+const INT_SUFFIXES: [&str; 20] = [
+    "", "u", "l", "ul", "lu", "ll", "ull", "llu", "u8", "i8", "u16", "i16", "u32", "i32", "u64",
+    "i64", "u128", "i128", "usize", "isize",
+];
+
+struct LitParser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    res: Vec<u8>,
+    found: bool,
+}
+
+impl<'a> LitParser<'a> {
+    fn new(src: &'a [u8]) -> Self {
+        // skip the declaration part: "unsigned char buf[16] = ", "let x: [u8; 2] = "
+        let start = match src.iter().position(|c| matches!(c, b'=' | b'"' | b'\'')) {
+            Some(p) if src[p] == b'=' => p + 1,
+            _ => 0,
+        };
+        Self {
+            src,
+            pos: start,
+            res: Vec::new(),
+            found: false,
+        }
+    }
+
+    fn peek(&self, off: usize) -> Option<u8> {
+        self.src.get(self.pos + off).copied()
+    }
+
+    fn parse(mut self) -> Result<Vec<u8>> {
+        while let Some(c) = self.peek(0) {
+            match c {
+                b'"' | b'\'' => self.parse_string(false)?,
+                b'/' if self.peek(1) == Some(b'/') => self.skip_line(),
+                b'#' => self.skip_line(),
+                b'/' if self.peek(1) == Some(b'*') => {
+                    let end = self.src[self.pos + 2..]
+                        .windows(2)
+                        .position(|w| w == b"*/")
+                        .context("Unterminated comment")?;
+                    self.pos += end + 4;
+                }
+                b'0'..=b'9' => self.parse_number(false)?,
+                b'-' if self.peek(1).is_some_and(|c| c.is_ascii_digit()) => {
+                    self.pos += 1;
+                    self.parse_number(true)?
+                }
+                c if c.is_ascii_alphabetic() || c == b'_' => self.parse_word()?,
+                b'{' | b'[' => {
+                    // empty arrays are valid literals: {}, &[], new Uint8Array([])
+                    let close = if c == b'{' { b'}' } else { b']' };
+                    self.pos += 1;
+                    while self.peek(0).is_some_and(|c| c.is_ascii_whitespace()) {
+                        self.pos += 1;
+                    }
+                    if self.peek(0) == Some(close) {
+                        self.found = true;
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+        if !self.found {
+            bail!("No literal found in input");
+        }
+        Ok(self.res)
+    }
+
+    fn skip_line(&mut self) {
+        while self.peek(0).is_some_and(|c| c != b'\n') {
+            self.pos += 1;
+        }
+    }
+
+    /* Identifiers are skipped, unless they are a string prefix (b"", br#""#, u8"") */
+    fn parse_word(&mut self) -> Result<()> {
+        let start = self.pos;
+        while self
+            .peek(0)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.pos += 1;
+        }
+        let word = self.src[start..self.pos].to_ascii_lowercase();
+        let is_prefix = matches!(
+            &word[..],
+            b"b" | b"r" | b"br" | b"rb" | b"u" | b"u8" | b"l" | b"ur"
+        );
+        match self.peek(0) {
+            Some(b'"') | Some(b'\'') if is_prefix => self.parse_string(word.contains(&b'r')),
+            Some(b'#') if is_prefix && word.contains(&b'r') => self.parse_rust_raw(),
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_rust_raw(&mut self) -> Result<()> {
+        let hashes = self.src[self.pos..]
+            .iter()
+            .take_while(|c| **c == b'#')
+            .count();
+        self.pos += hashes;
+        if self.peek(0) != Some(b'"') {
+            bail!("Invalid raw string at offset {}", self.pos);
+        }
+        self.pos += 1;
+        let mut end = vec![b'"'];
+        end.resize(hashes + 1, b'#');
+        let len = self.src[self.pos..]
+            .windows(end.len())
+            .position(|w| w == end)
+            .context("Unterminated raw string")?;
+        self.res
+            .extend_from_slice(&self.src[self.pos..self.pos + len]);
+        self.pos += len + end.len();
+        self.found = true;
+        Ok(())
+    }
+
+    fn parse_string(&mut self, raw: bool) -> Result<()> {
+        let quote = self.src[self.pos];
+        // Python triple quoted strings
+        let delim_len = if self.peek(1) == Some(quote) && self.peek(2) == Some(quote) {
+            3
+        } else {
+            1
+        };
+        self.pos += delim_len;
+        let delim = vec![quote; delim_len];
+        loop {
+            if self.src[self.pos..].starts_with(&delim) {
+                self.pos += delim_len;
+                self.found = true;
+                return Ok(());
+            }
+            match self.peek(0) {
+                None => bail!("Unterminated string literal"),
+                Some(b'\\') if !raw => {
+                    self.pos += 1;
+                    self.parse_escape()?
+                }
+                Some(b'\\') if self.peek(1).is_some() => {
+                    // raw strings keep the backslash, but an escaped quote does not end them
+                    self.res
+                        .extend_from_slice(&self.src[self.pos..self.pos + 2]);
+                    self.pos += 2;
+                }
+                Some(c) => {
+                    self.res.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /* Read up to max digits in the given radix */
+    fn read_digits(&mut self, radix: u32, max: usize) -> Option<u32> {
+        let mut val: u32 = 0;
+        let mut count = 0;
+        while count < max {
+            match self.peek(0).and_then(|c| (c as char).to_digit(radix)) {
+                Some(d) => {
+                    val = val * radix + d;
+                    self.pos += 1;
+                    count += 1;
+                }
+                None => break,
+            }
+        }
+        (count > 0).then_some(val)
+    }
+
+    fn push_char(&mut self, code: u32) -> Result<()> {
+        let c = char::from_u32(code)
+            .with_context(|| format!("Invalid unicode escape: 0x{:x}", code))?;
+        let mut buf = [0; 4];
+        self.res
+            .extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        Ok(())
+    }
+
+    fn parse_escape(&mut self) -> Result<()> {
+        let c = self.peek(0).context("Unterminated string literal")?;
+        self.pos += 1;
+        let b = match c {
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'e' => 0x1b,
+            b'\\' | b'\'' | b'"' | b'?' => c,
+            // line continuation
+            b'\n' => return Ok(()),
+            b'x' => self
+                .read_digits(16, 2)
+                .with_context(|| format!("Invalid \\x escape at offset {}", self.pos))?
+                as u8,
+            b'0'..=b'7' => {
+                self.pos -= 1;
+                let val = self.read_digits(8, 3).unwrap();
+                if val > 0xff {
+                    bail!("Octal escape out of range: \\{:o}", val);
+                }
+                val as u8
+            }
+            b'u' if self.peek(0) == Some(b'{') => {
+                self.pos += 1;
+                let code = self.read_digits(16, 6).context("Invalid \\u{} escape")?;
+                if self.peek(0) != Some(b'}') {
+                    bail!("Invalid \\u{{}} escape at offset {}", self.pos);
+                }
+                self.pos += 1;
+                return self.push_char(code);
+            }
+            b'u' | b'U' => {
+                let len = if c == b'u' { 4 } else { 8 };
+                let start = self.pos;
+                let code = self.read_digits(16, len);
+                match code {
+                    Some(code) if self.pos - start == len => return self.push_char(code),
+                    _ => bail!("Invalid \\{} escape at offset {}", c as char, start),
+                }
+            }
+            // unknown escapes are kept as is, like Python does
+            _ => {
+                self.res.push(b'\\');
+                c
+            }
+        };
+        self.res.push(b);
+        Ok(())
+    }
+
+    fn parse_number(&mut self, negative: bool) -> Result<()> {
+        let start = self.pos;
+        while self
+            .peek(0)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.pos += 1;
+        }
+        let token: String = String::from_utf8_lossy(&self.src[start..self.pos])
+            .to_ascii_lowercase()
+            .replace('_', "");
+        let (radix, digits) = if let Some(d) = token.strip_prefix("0x") {
+            (16, d)
+        } else if let Some(d) = token.strip_prefix("0b") {
+            (2, d)
+        } else if let Some(d) = token.strip_prefix("0o") {
+            (8, d)
+        } else if token.len() > 1 && token.starts_with('0') && token.as_bytes()[1].is_ascii_digit()
+        {
+            // C octal
+            (8, &token[1..])
+        } else {
+            (10, &token[..])
+        };
+        let split = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        let (digits, suffix) = digits.split_at(split);
+        if digits.is_empty() || !INT_SUFFIXES.contains(&suffix) {
+            bail!("Invalid number: {}", token);
+        }
+        let val = i64::from_str_radix(digits, radix)
+            .with_context(|| format!("Invalid number: {}", token))?;
+        let val = if negative { -val } else { val };
+        if !(-128..=255).contains(&val) {
+            bail!("Value out of byte range: {}", val);
+        }
+        self.res.push(val as u8);
+        self.found = true;
+        Ok(())
+    }
+}
+
+pub struct ParseLitApplet {}
+
+impl Applet for ParseLitApplet {
+    fn command(&self) -> &'static str {
+        "parselit"
+    }
+    fn description(&self) -> &'static str {
+        "recover bytes from a C/Python/Rust/JS literal"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Accepts string literals (\"..\", b'..', br#\"..\"#, ...) and arrays of \
+                 numbers or chars ({0x41, 66, 'C'}, [0x41u8, 0o102], ...). Several literals \
+                 are concatenated, declarations before '=' and comments are ignored.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {})
+    }
+
+    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {}))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        LitParser::new(&val).parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["parselit"])
+            .write_stdin("buf = b\"\\x41B\\n\\101\"\n")
+            .assert()
+            .stdout("AB\nA")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["parselit", "hello"])
+            .assert()
+            .stderr(predicates::str::contains("No literal found"))
+            .failure();
+    }
+
+    #[test]
+    fn test_python() {
+        let pl = ParseLitApplet {};
+        assert_eq!(
+            pl.process_test(b"b'\\x00\\xffA\\'\\\\\\t\\0'".to_vec()),
+            b"\x00\xffA'\\\t\x00"
+        );
+        assert_eq!(pl.process_test(b"b\"\"".to_vec()), b"");
+        assert_eq!(
+            pl.process_test(b"x = (b\"AB\"  # comment\n     b'CD')".to_vec()),
+            b"ABCD"
+        );
+        assert_eq!(pl.process_test(b"rb'\\x41'".to_vec()), b"\\x41");
+        assert_eq!(pl.process_test(b"b'''a\"'b'''".to_vec()), b"a\"'b");
+        assert_eq!(pl.process_test(b"'\\u00e9\\q'".to_vec()), "é\\q".as_bytes());
+        assert!(pl.process(b"b'\\x'".to_vec()).is_err());
+        assert!(pl.process(b"b'AB".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_c() {
+        let pl = ParseLitApplet {};
+        assert_eq!(
+            pl.process_test(
+                b"unsigned char buf[4] = {0x41, 66, '\\n', -1, 0103, 'D'}; /* 5 */".to_vec()
+            ),
+            b"AB\n\xffCD"
+        );
+        assert_eq!(
+            pl.process_test(b"char *s = \"a\\x62\" \"c\\\n\\?\";".to_vec()),
+            b"abc?"
+        );
+        assert_eq!(pl.process_test(b"{0xFFu, 1UL}".to_vec()), b"\xff\x01");
+        assert_eq!(pl.process_test(b"char buf[] = { };".to_vec()), b"");
+        assert!(pl.process(b"{256}".to_vec()).is_err());
+        assert!(pl.process(b"{0x41z}".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_rust_js() {
+        let pl = ParseLitApplet {};
+        assert_eq!(
+            pl.process_test(b"let x: [u8; 3] = [0x41u8, 0o102, 0b0100_0011];".to_vec()),
+            b"ABC"
+        );
+        assert_eq!(
+            pl.process_test(b"br##\"a\"#b\"##, b'\\x41', \"\\u{e9}\"".to_vec()),
+            "a\"#bAé".as_bytes()
+        );
+        assert_eq!(
+            pl.process_test(b"const a = new Uint8Array([0x41, 0x42]);".to_vec()),
+            b"AB"
+        );
+        assert_eq!(pl.process_test(b"&[]".to_vec()), b"");
+        assert_eq!(pl.process_test(b"new Uint8Array([])".to_vec()), b"");
+        assert!(pl.process(b"[x]".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::applet::{build_applet, Applet};
+use crate::b64app::B64EncApplet;
+use crate::hexapp::HexApplet;
+use crate::urlapp::UrlEncApplet;
+use anyhow::{bail, Result};
+use clap::{arg, Command};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Variant {
+    Raw,
+    Urlenc,
+    DoubleUrlenc,
+    B64,
+    Hex,
+}
+
+const ALL_VARIANTS: [Variant; 5] = [
+    Variant::Raw,
+    Variant::Urlenc,
+    Variant::DoubleUrlenc,
+    Variant::B64,
+    Variant::Hex,
+];
+
+impl Variant {
+    fn encode(&self, val: &[u8]) -> Result<Vec<u8>> {
+        let urlenc = || build_applet(UrlEncApplet::new().as_ref(), &[]);
+        match self {
+            Variant::Raw => Ok(val.to_vec()),
+            Variant::Urlenc => urlenc()?.process(val.to_vec()),
+            Variant::DoubleUrlenc => {
+                let app = urlenc()?;
+                app.process(app.process(val.to_vec())?)
+            }
+            Variant::B64 => B64EncApplet::new().process(val.to_vec()),
+            Variant::Hex => HexApplet::new().process(val.to_vec()),
+        }
+    }
+}
+
+pub struct PayloadsApplet {
+    variants: Vec<Variant>,
+}
+
+impl Applet for PayloadsApplet {
+    fn command(&self) -> &'static str {
+        "payloads"
+    }
+    fn description(&self) -> &'static str {
+        "output encoded variants of the input, one per line"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-v --variants <LIST> "comma-separated list of variants, all by default")
+                    .value_parser(clap::builder::EnumValueParser::<Variant>::new())
+                    .value_delimiter(','),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "The output can be used as a payload list for intruder/fuzzer style tools.\n\
+                 The raw variant is skipped when the input contains line breaks.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            variants: ALL_VARIANTS.to_vec(),
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let variants = match args.get_many::<Variant>("variants") {
+            Some(v) => v.copied().collect(),
+            None => ALL_VARIANTS.to_vec(),
+        };
+        Ok(Box::new(Self { variants }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        /* A raw payload with line breaks would span several lines of the list */
+        let multiline = val.iter().any(|c| matches!(c, b'\n' | b'\r'));
+        if multiline && self.variants.contains(&Variant::Raw) {
+            eprintln!("Input contains line breaks, skipping the raw variant");
+        }
+        let variants: Vec<&Variant> = self
+            .variants
+            .iter()
+            .filter(|v| !(multiline && **v == Variant::Raw))
+            .collect();
+        if variants.is_empty() {
+            bail!("No variant to output");
+        }
+        let encoded = variants
+            .iter()
+            .map(|v| v.encode(&val))
+            .collect::<Result<Vec<Vec<u8>>>>()?;
+        Ok(encoded.join(&b'\n'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["payloads", "-v", "hex,urlenc", "<a>"])
+            .assert()
+            .stdout("3c613e\n%3ca%3e")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["payloads", "-v", "raw,hex"])
+            .write_stdin("<a>\n")
+            .assert()
+            .stdout("3c613e0a")
+            .stderr(predicates::str::contains("skipping the raw variant"))
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["payloads", "-v", "raw"])
+            .write_stdin("a\r\nb")
+            .assert()
+            .stderr(predicates::str::contains("No variant to output"))
+            .failure();
+    }
+
+    #[test]
+    fn test_all_variants() {
+        let payloads = PayloadsApplet::new();
+        let res = String::from_utf8(payloads.process_test(b"' or 1=1".to_vec())).unwrap();
+        let lines: Vec<&str> = res.split('\n').collect();
+        assert_eq!(
+            lines,
+            [
+                "' or 1=1",
+                "%27%20or%201%3d1",
+                "%2527%2520or%25201%253d1",
+                "JyBvciAxPTE=",
+                "27206f7220313d31",
+            ]
+        );
+
+        let res = String::from_utf8(payloads.process_test(b"a\nb".to_vec())).unwrap();
+        assert_eq!(res.lines().count(), 4);
+        assert!(!res.lines().any(|l| l == "a" || l == "b"));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::applet::Applet;
+use anyhow::{bail, Result};
+use clap::{arg, value_parser, Command};
+
+pub struct Pkcs7Applet {
+    block: usize,
+    strip: bool,
+}
+
+/// Pad val to a multiple of block bytes. A full block of padding is added
+/// if val is already aligned.
+pub fn pkcs7_pad(val: &[u8], block: usize) -> Vec<u8> {
+    let pad = block - (val.len() % block);
+    let mut res = Vec::with_capacity(val.len() + pad);
+    res.extend_from_slice(val);
+    res.resize(val.len() + pad, pad as u8);
+    res
+}
+
+/// Validate and remove PKCS#7 padding.
+pub fn pkcs7_unpad(val: &[u8], block: usize) -> Result<Vec<u8>> {
+    if val.is_empty() || val.len() % block != 0 {
+        bail!(
+            "Invalid padding: length {} is not a multiple of the block size {}",
+            val.len(),
+            block
+        );
+    }
+    let pad = *val.last().unwrap() as usize;
+    if pad == 0 || pad > block {
+        bail!("Invalid padding: last byte is 0x{:02x}", pad);
+    }
+    if let Some(i) = val[val.len() - pad..]
+        .iter()
+        .position(|&b| b as usize != pad)
+    {
+        bail!(
+            "Invalid padding: byte at offset 0x{:x} should be 0x{:02x}",
+            val.len() - pad + i,
+            pad
+        );
+    }
+    Ok(val[..val.len() - pad].to_vec())
+}
+
+impl Applet for Pkcs7Applet {
+    fn command(&self) -> &'static str {
+        "pkcs7"
+    }
+    fn description(&self) -> &'static str {
+        "add or strip PKCS#7 padding"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-b --block <N> "block size, in bytes")
+                    .value_parser(value_parser!(u16).range(1..=255))
+                    .default_value("16"),
+            )
+            .arg(arg!(-r --reverse "validate and strip the padding").visible_alias("strip"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            block: 16,
+            strip: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            block: *args.get_one::<u16>("block").unwrap() as usize,
+            strip: args.get_flag("reverse"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.strip {
+            pkcs7_unpad(&val, self.block)
+        } else {
+            Ok(pkcs7_pad(&val, self.block))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["pkcs7", "-b", "8", "YELLOW"])
+            .assert()
+            .stdout("YELLOW\x02\x02")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["pkcs7", "--strip", "-b", "8"])
+            .write_stdin("YELLOW\x02\x02")
+            .assert()
+            .stdout("YELLOW")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["pkcs7", "-r"])
+            .write_stdin("ICE ICE BABY\x01\x02\x03\x04")
+            .assert()
+            .stderr(predicates::str::contains("Invalid padding"))
+            .failure();
+    }
+
+    #[test]
+    fn test_pad() {
+        let pad = Pkcs7Applet {
+            block: 16,
+            strip: false,
+        };
+        assert_eq!(
+            pad.process_test(b"YELLOW SUBMARINE".to_vec()),
+            [b"YELLOW SUBMARINE".to_vec(), vec![16; 16]].concat()
+        );
+        assert_eq!(pad.process_test(vec![]), vec![16; 16]);
+        assert_eq!(
+            pad.process_test(b"YELLOW SUBMARIN".to_vec()),
+            b"YELLOW SUBMARIN\x01"
+        );
+    }
+
+    #[test]
+    fn test_strip() {
+        let strip = Pkcs7Applet {
+            block: 16,
+            strip: true,
+        };
+        assert_eq!(
+            strip.process_test(b"ICE ICE BABY\x04\x04\x04\x04".to_vec()),
+            b"ICE ICE BABY"
+        );
+        assert_eq!(strip.process_test(vec![16; 16]), b"");
+        // wrong padding bytes, zero, too large, unaligned length
+        assert!(strip
+            .process(b"ICE ICE BABY\x05\x05\x05\x05".to_vec())
+            .is_err());
+        assert!(strip
+            .process(b"ICE ICE BABY\x01\x02\x03\x04".to_vec())
+            .is_err());
+        assert!(strip
+            .process(b"ICE ICE BABY\x04\x04\x04\x00".to_vec())
+            .is_err());
+        assert!(strip.process(vec![17; 16]).is_err());
+        assert!(strip.process(b"ICE ICE BABY\x01".to_vec()).is_err());
+        assert!(strip.process(vec![]).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+use crate::applet::Applet;
+use crate::json::JsonFormat;
+use crate::varintapp::decode_uleb128;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+use std::convert::{TryFrom, TryInto};
+
+/* Maximum nesting level, to avoid blowing the stack on malicious input */
+const MAX_DEPTH: usize = 64;
+
+/* Decoded field values, groups and nested messages hold their fields */
+#[derive(Debug, PartialEq)]
+enum FieldValue {
+    Varint(u64),
+    I64(u64),
+    Len(Vec<u8>),
+    Message(Vec<Field>),
+    Group(Vec<Field>),
+    I32(u32),
+}
+
+#[derive(Debug, PartialEq)]
+struct Field {
+    number: u64,
+    value: FieldValue,
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    recurse: bool,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        match self.data.get(self.pos..self.pos.saturating_add(len)) {
+            Some(d) => {
+                self.pos += len;
+                Ok(d)
+            }
+            None => bail!("Truncated input at offset {}", self.pos),
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let (val, len) = decode_uleb128(&self.data[self.pos..])
+            .with_context(|| format!("Invalid varint at offset {}", self.pos))?;
+        self.pos += len;
+        Ok(val)
+    }
+
+    /* Parse fields until the end of the data, or the end of the given group */
+    fn fields(&mut self, depth: usize, group: Option<u64>) -> Result<Vec<Field>> {
+        if depth > MAX_DEPTH {
+            bail!("Maximum nesting depth reached");
+        }
+        let mut res = vec![];
+        while self.pos < self.data.len() {
+            let offset = self.pos;
+            let key = self.varint()?;
+            let number = key >> 3;
+            if number == 0 {
+                bail!("Invalid field number 0 at offset {}", offset);
+            }
+            let value = match key & 7 {
+                0 => FieldValue::Varint(self.varint()?),
+                1 => FieldValue::I64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+                2 => {
+                    let len = self.varint()?;
+                    let data = self.take(usize::try_from(len).unwrap_or(usize::MAX))?;
+                    self.len_value(data, depth)
+                }
+                3 => FieldValue::Group(self.fields(depth + 1, Some(number))?),
+                4 if group == Some(number) => return Ok(res),
+                4 => bail!("Unexpected end of group {} at offset {}", number, offset),
+                5 => FieldValue::I32(u32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+                t => bail!("Invalid wire type {} at offset {}", t, offset),
+            };
+            res.push(Field { number, value });
+        }
+        if let Some(number) = group {
+            bail!("Missing end of group {}", number);
+        }
+        Ok(res)
+    }
+
+    /* Length-delimited data is a nested message if it parses as one */
+    fn len_value(&self, data: &[u8], depth: usize) -> FieldValue {
+        if self.recurse && !data.is_empty() {
+            let mut sub = Decoder {
+                data,
+                pos: 0,
+                recurse: true,
+            };
+            if let Ok(fields) = sub.fields(depth + 1, None) {
+                return FieldValue::Message(fields);
+            }
+        }
+        FieldValue::Len(data.to_vec())
+    }
+}
+
+/* Printable data is shown as a string, other data as hex with an ASCII preview */
+fn render_bytes(data: &[u8], res: &mut String) {
+    if data.iter().all(|c| c.is_ascii_graphic() || *c == b' ') {
+        res.push('"');
+        for c in data {
+            if matches!(c, b'"' | b'\\') {
+                res.push('\\');
+            }
+            res.push(*c as char);
+        }
+        res.push('"');
+    } else {
+        res.push_str(&hex::encode(data));
+        res.push_str(" |");
+        res.extend(data.iter().map(|c| {
+            if c.is_ascii_graphic() || *c == b' ' {
+                *c as char
+            } else {
+                '.'
+            }
+        }));
+        res.push('|');
+    }
+}
+
+fn render(fields: &[Field], indent: usize, res: &mut String) {
+    let pad = "  ".repeat(indent);
+    for f in fields {
+        res.push_str(&pad);
+        match &f.value {
+            FieldValue::Varint(v) => {
+                res.push_str(&format!("{} (varint): {}", f.number, v));
+                if (*v as i64) < 0 {
+                    res.push_str(&format!(" ({})", *v as i64));
+                }
+            }
+            FieldValue::I64(v) => res.push_str(&format!(
+                "{} (i64): 0x{:016x} (double: {:?})",
+                f.number,
+                v,
+                f64::from_bits(*v)
+            )),
+            FieldValue::I32(v) => res.push_str(&format!(
+                "{} (i32): 0x{:08x} (float: {:?})",
+                f.number,
+                v,
+                f32::from_bits(*v)
+            )),
+            FieldValue::Len(d) => {
+                res.push_str(&format!("{} (len): ", f.number));
+                render_bytes(d, res);
+            }
+            FieldValue::Message(sub) | FieldValue::Group(sub) => {
+                let wire = match f.value {
+                    FieldValue::Group(_) => "group",
+                    _ => "len",
+                };
+                res.push_str(&format!("{} ({}) {{\n", f.number, wire));
+                render(sub, indent + 1, res);
+                res.push_str(&pad);
+                res.push('}');
+            }
+        }
+        res.push('\n');
+    }
+}
+
+/* JSON output: an array of {"field", "type", "value"} objects. Length-delimited
+ * values are in hex, with a "text" member when printable */
+fn to_json(fields: &[Field]) -> serde_json::Value {
+    let fields = fields.iter().map(|f| {
+        let (wire, value) = match &f.value {
+            FieldValue::Varint(v) => ("varint", serde_json::json!(v)),
+            FieldValue::I64(v) => ("i64", serde_json::json!(v)),
+            FieldValue::I32(v) => ("i32", serde_json::json!(v)),
+            FieldValue::Len(d) => ("len", serde_json::json!(hex::encode(d))),
+            FieldValue::Message(sub) => ("len", to_json(sub)),
+            FieldValue::Group(sub) => ("group", to_json(sub)),
+        };
+        let mut res = serde_json::json!({"field": f.number, "type": wire, "value": value});
+        if let FieldValue::Len(d) = &f.value {
+            if d.iter().all(|c| c.is_ascii_graphic() || *c == b' ') {
+                res["text"] = serde_json::json!(String::from_utf8_lossy(d));
+            }
+        }
+        res
+    });
+    serde_json::Value::Array(fields.collect())
+}
+
+pub struct ProtobufApplet {
+    recurse: bool,
+    json: Option<JsonFormat>,
+}
+
+impl Applet for ProtobufApplet {
+    fn command(&self) -> &'static str {
+        "protobuf"
+    }
+    fn description(&self) -> &'static str {
+        "dump protobuf wire format data, without schema"
+    }
+
+    fn clap_command(&self) -> Command {
+        JsonFormat::add_args(Command::new(self.command()))
+            .about(self.description())
+            .arg(arg!(-r --recurse "show length-delimited fields which parse as messages as nested messages"))
+            .arg(arg!(-j --json "output JSON"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Outputs one line per field: number (wire type): value.\n\
+                 Negative varints are also shown as signed, fixed-size values as floats.\n\
+                 --indent and --compact apply to --json output.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            recurse: false,
+            json: None,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            recurse: args.get_flag("recurse"),
+            json: args.get_flag("json").then(|| JsonFormat::from_args(args)),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let mut dec = Decoder {
+            data: &val,
+            pos: 0,
+            recurse: self.recurse,
+        };
+        let fields = dec.fields(0, None)?;
+        let mut res = String::new();
+        match self.json {
+            Some(fmt) => {
+                fmt.render(&to_json(&fields), 0, &mut res);
+                res.push('\n');
+            }
+            None => render(&fields, 0, &mut res),
+        }
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* 1: 150, 2: "testing", 3: {1: 150} */
+    const MSG: &[u8] = b"\x08\x96\x01\x12\x07testing\x1a\x03\x08\x96\x01";
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["protobuf", "--recurse"])
+            .write_stdin(MSG)
+            .assert()
+            .stdout("1 (varint): 150\n2 (len): \"testing\"\n3 (len) {\n  1 (varint): 150\n}\n")
+            .success();
+    }
+
+    #[test]
+    fn test_json() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["protobuf", "-r", "--json", "--compact"])
+            .write_stdin(MSG)
+            .assert()
+            .stdout(
+                "[{\"field\":1,\"type\":\"varint\",\"value\":150},\
+                 {\"field\":2,\"type\":\"len\",\"value\":\"74657374696e67\",\"text\":\"testing\"},\
+                 {\"field\":3,\"type\":\"len\",\"value\":[{\"field\":1,\"type\":\"varint\",\"value\":150}]}]\n",
+            )
+            .success();
+
+        let pb = ProtobufApplet {
+            recurse: false,
+            json: Some(JsonFormat::default()),
+        };
+        assert_eq!(
+            String::from_utf8(pb.process_test(b"\x08\x01".to_vec())).unwrap(),
+            "[\n  {\n    \"field\": 1,\n    \"type\": \"varint\",\n    \"value\": 1\n  }\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let pb = ProtobufApplet {
+            recurse: false,
+            json: None,
+        };
+        assert_eq!(
+            String::from_utf8(pb.process_test(MSG.to_vec())).unwrap(),
+            "1 (varint): 150\n2 (len): \"testing\"\n3 (len): 089601 |...|\n"
+        );
+
+        // fixed-size values, negative varint, group
+        let msg = b"\x09\x00\x00\x00\x00\x00\x00\xf0\x3f\
+                    \x15\x00\x00\x80\x3f\
+                    \x18\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01\
+                    \x23\x08\x01\x24";
+        assert_eq!(
+            String::from_utf8(pb.process_test(msg.to_vec())).unwrap(),
+            "1 (i64): 0x3ff0000000000000 (double: 1.0)\n\
+             2 (i32): 0x3f800000 (float: 1.0)\n\
+             3 (varint): 18446744073709551615 (-1)\n\
+             4 (group) {\n  1 (varint): 1\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let pb = ProtobufApplet {
+            recurse: true,
+            json: None,
+        };
+        // truncated length-delimited field, truncated varint
+        assert!(pb.process(b"\x12\x07test".to_vec()).is_err());
+        assert!(pb.process(b"\x08\x96".to_vec()).is_err());
+        // field number 0, wire type 7, unterminated group
+        assert!(pb.process(b"\x00\x01".to_vec()).is_err());
+        assert!(pb.process(b"\x0f\x01".to_vec()).is_err());
+        assert!(pb.process(b"\x0b\x08\x01".to_vec()).is_err());
+        // data which does not parse as a message stays raw
+        assert_eq!(
+            pb.process_test(b"\x0a\x02\xff\xff".to_vec()),
+            b"1 (len): ffff |..|\n"
+        );
+    }
+}
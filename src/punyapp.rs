@@ -0,0 +1,163 @@
+use crate::applet::{Applet, SliceExt};
+use anyhow::{anyhow, Context, Result};
+use clap::{arg, Command};
+
+const ACE_PREFIX: &str = "xn--";
+
+pub struct PunyApplet {
+    reverse: bool,
+    label: bool,
+}
+
+impl PunyApplet {
+    /* IDNA: only non-ASCII labels are encoded, with the "xn--" prefix */
+    fn encode_domain(domain: &str) -> Result<String> {
+        let labels: Result<Vec<String>> = domain
+            .split('.')
+            .map(|l| {
+                if l.is_ascii() {
+                    Ok(l.to_string())
+                } else {
+                    let enc = punycode::encode(l)
+                        .map_err(|_| anyhow!("Could not encode label \"{}\"", l))?;
+                    Ok(format!("{}{}", ACE_PREFIX, enc))
+                }
+            })
+            .collect();
+        Ok(labels?.join("."))
+    }
+
+    fn decode_domain(domain: &str) -> Result<String> {
+        let labels: Result<Vec<String>> = domain
+            .split('.')
+            .map(|l| {
+                if l.len() > ACE_PREFIX.len()
+                    && l.get(..ACE_PREFIX.len())
+                        .is_some_and(|p| p.eq_ignore_ascii_case(ACE_PREFIX))
+                {
+                    punycode::decode(&l[ACE_PREFIX.len()..])
+                        .map_err(|_| anyhow!("Invalid punycode label \"{}\"", l))
+                } else {
+                    Ok(l.to_string())
+                }
+            })
+            .collect();
+        Ok(labels?.join("."))
+    }
+}
+
+impl Applet for PunyApplet {
+    fn command(&self) -> &'static str {
+        "puny"
+    }
+    fn description(&self) -> &'static str {
+        "punycode (IDNA) encode/decode"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-r --reverse "decode punycode"))
+            .arg(arg!(-l --label "input is a single label: raw punycode, without 'xn--' prefix"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help("By default, the input is a domain name and each label is processed separately.\nNote that no IDNA mapping (such as case folding) is done.")
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            reverse: false,
+            label: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            reverse: args.get_flag("reverse"),
+            label: args.get_flag("label"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let input = std::str::from_utf8(val.trim()).context("Input is not valid UTF-8")?;
+        let res = match (self.reverse, self.label) {
+            (false, false) => Self::encode_domain(input)?,
+            (true, false) => Self::decode_domain(input)?,
+            (false, true) => {
+                punycode::encode(input).map_err(|_| anyhow!("Could not encode label"))?
+            }
+            (true, true) => {
+                punycode::decode(input).map_err(|_| anyhow!("Invalid punycode label"))?
+            }
+        };
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["puny", "www.münchen.de"])
+            .assert()
+            .stdout("www.xn--mnchen-3ya.de")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["puny", "-r"])
+            .write_stdin("www.XN--mnchen-3ya.de\n")
+            .assert()
+            .stdout("www.münchen.de")
+            .success();
+    }
+
+    #[test]
+    fn test_domain() {
+        let puny = PunyApplet {
+            reverse: false,
+            label: false,
+        };
+        let unpuny = PunyApplet {
+            reverse: true,
+            label: false,
+        };
+        assert_eq!(
+            puny.process_test("münchen.example".as_bytes().to_vec()),
+            b"xn--mnchen-3ya.example"
+        );
+        assert_eq!(
+            unpuny.process_test(b"xn--mnchen-3ya.example".to_vec()),
+            "münchen.example".as_bytes()
+        );
+        assert_eq!(puny.process_test(b"example.com".to_vec()), b"example.com");
+        assert!(unpuny.process(b"xn--\xc3\xa9".to_vec()).is_err());
+        assert_eq!(
+            unpuny.process_test("abc\u{e9}.com".as_bytes().to_vec()),
+            "abc\u{e9}.com".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_label() {
+        let puny = PunyApplet {
+            reverse: false,
+            label: true,
+        };
+        let unpuny = PunyApplet {
+            reverse: true,
+            label: true,
+        };
+        assert_eq!(
+            puny.process_test("münchen".as_bytes().to_vec()),
+            b"mnchen-3ya"
+        );
+        assert_eq!(
+            unpuny.process_test(b"mnchen-3ya".to_vec()),
+            "münchen".as_bytes()
+        );
+    }
+}
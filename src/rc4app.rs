@@ -0,0 +1,142 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+pub struct Rc4Applet {
+    key: Vec<u8>,
+}
+
+struct Rc4 {
+    s: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    /* Key-scheduling algorithm */
+    fn new(key: &[u8]) -> Self {
+        let mut s = [0u8; 256];
+        for (i, v) in s.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        let mut j: u8 = 0;
+        for i in 0..256 {
+            j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+            s.swap(i, j as usize);
+        }
+        Self { s, i: 0, j: 0 }
+    }
+
+    /* Pseudo-random generation algorithm, one keystream byte */
+    fn next_byte(&mut self) -> u8 {
+        self.i = self.i.wrapping_add(1);
+        self.j = self.j.wrapping_add(self.s[self.i as usize]);
+        self.s.swap(self.i as usize, self.j as usize);
+        self.s[self.s[self.i as usize].wrapping_add(self.s[self.j as usize]) as usize]
+    }
+}
+
+impl Applet for Rc4Applet {
+    fn command(&self) -> &'static str {
+        "rc4"
+    }
+    fn description(&self) -> &'static str {
+        "RC4 encryption/decryption"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-k --key <HEX> "key, in hex")
+                    .required_unless_present("key-str")
+                    .conflicts_with("key-str"),
+            )
+            .arg(arg!(-s --"key-str" <KEY> "key, as a string"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self { key: vec![] })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let key = match args.get_one::<String>("key") {
+            Some(k) => hex::decode(k).context("Invalid hex in key")?,
+            None => args
+                .get_one::<String>("key-str")
+                .unwrap()
+                .as_bytes()
+                .to_vec(),
+        };
+        if key.is_empty() || key.len() > 256 {
+            bail!("Invalid key length {}, must be 1 to 256 bytes", key.len());
+        }
+        Ok(Box::new(Self { key }))
+    }
+
+    fn explain(&self) -> String {
+        format!(
+            "rc4: xor the input with the RC4 keystream of a {} bytes key.",
+            self.key.len()
+        )
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let mut rc4 = Rc4::new(&self.key);
+        Ok(val.iter().map(|b| b ^ rc4.next_byte()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::applet::build_applet;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["rc4", "--key-str", "Key", "Plaintext"])
+            .assert()
+            .stdout(predicates::ord::eq(
+                hex::decode("bbf316e8d940af0ad3").unwrap(),
+            ))
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["rc4", "-k", "536563726574"])
+            .write_stdin(hex::decode("45a01f645fc35b383552544b9bf5").unwrap())
+            .assert()
+            .stdout("Attack at dawn")
+            .success();
+    }
+
+    #[test]
+    fn test_vectors() {
+        let rc4 = Rc4Applet {
+            key: b"Wiki".to_vec(),
+        };
+        assert_eq!(
+            hex::encode(rc4.process_test(b"pedia".to_vec())),
+            "1021bf0420"
+        );
+
+        // symmetric
+        let rc4 = Rc4Applet {
+            key: b"Key".to_vec(),
+        };
+        let enc = rc4.process_test(b"Plaintext".to_vec());
+        assert_eq!(rc4.process_test(enc), b"Plaintext");
+    }
+
+    #[test]
+    fn test_explain() {
+        let rc4 = build_applet(Rc4Applet::new().as_ref(), &["-s", "Secret"]).unwrap();
+        assert_eq!(
+            rc4.explain(),
+            "rc4: xor the input with the RC4 keystream of a 6 bytes key."
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+pub struct ReplaceApplet {
+    find: Vec<u8>,
+    to: Vec<u8>,
+    count: bool,
+}
+
+impl ReplaceApplet {
+    /* Replace non-overlapping occurrences, from left to right.
+     * Returns the result and the number of replacements */
+    fn replace(&self, val: &[u8]) -> (Vec<u8>, usize) {
+        let mut res = Vec::with_capacity(val.len());
+        let mut count = 0;
+        let mut i = 0;
+        while i < val.len() {
+            if val[i..].starts_with(&self.find) {
+                res.extend_from_slice(&self.to);
+                i += self.find.len();
+                count += 1;
+            } else {
+                res.push(val[i]);
+                i += 1;
+            }
+        }
+        (res, count)
+    }
+}
+
+impl Applet for ReplaceApplet {
+    fn command(&self) -> &'static str {
+        "replace"
+    }
+    fn description(&self) -> &'static str {
+        "replace a byte pattern"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-f --find <HEX> "pattern to replace, in hex").required(true))
+            .arg(
+                arg!(-t --to <HEX> "replacement, in hex, can be empty")
+                    .required_unless_present("count"),
+            )
+            .arg(arg!(-c --count "only output the number of occurrences"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help("Occurrences are replaced from left to right, and do not overlap.")
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            find: vec![],
+            to: vec![],
+            count: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let decode = |name: &str| -> Result<Vec<u8>> {
+            match args.get_one::<String>(name) {
+                Some(h) => hex::decode(h.replace(' ', ""))
+                    .with_context(|| format!("Invalid hex in --{}", name)),
+                None => Ok(vec![]),
+            }
+        };
+        let find = decode("find")?;
+        if find.is_empty() {
+            bail!("Pattern to find cannot be empty");
+        }
+        Ok(Box::new(Self {
+            find,
+            to: decode("to")?,
+            count: args.get_flag("count"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let (res, count) = self.replace(&val);
+        if self.count {
+            Ok(format!("{}\n", count).into_bytes())
+        } else {
+            Ok(res)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["replace", "-f", "4142", "-t", "58", "ABCABAAB"])
+            .assert()
+            .stdout("XCXAX")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["replace", "-f", "4141", "-c", "AAAAA"])
+            .assert()
+            .stdout("2\n")
+            .success();
+    }
+
+    #[test]
+    fn test_replace() {
+        // same length: patch a conditional jump
+        let patch = ReplaceApplet {
+            find: vec![0x74, 0x05],
+            to: vec![0xeb, 0x05],
+            count: false,
+        };
+        assert_eq!(
+            patch.process_test(vec![0x90, 0x74, 0x05, 0x90, 0x74, 0x05]),
+            [0x90, 0xeb, 0x05, 0x90, 0xeb, 0x05]
+        );
+
+        // longer replacement
+        let longer = ReplaceApplet {
+            find: b"a".to_vec(),
+            to: b"<a>".to_vec(),
+            count: false,
+        };
+        assert_eq!(longer.process_test(b"banana".to_vec()), b"b<a>n<a>n<a>");
+
+        // shorter, left to right without overlap: "aaa" matches once
+        let shorter = ReplaceApplet {
+            find: b"aa".to_vec(),
+            to: vec![],
+            count: false,
+        };
+        assert_eq!(shorter.process_test(b"aaab".to_vec()), b"ab");
+        assert_eq!(shorter.replace(b"aaaa"), (vec![], 2));
+        assert_eq!(shorter.replace(b""), (vec![], 0));
+    }
+}
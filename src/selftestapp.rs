@@ -0,0 +1,338 @@
+use crate::aesapp::AesApplet;
+use crate::applet::{build_applet, Applet};
+use crate::b45app::{B45DecApplet, B45EncApplet};
+use crate::b64app::{B64DecApplet, B64EncApplet};
+use crate::deltaapp::DeltaApplet;
+use crate::derefapp::{DerefApplet, PrefixLenApplet};
+use crate::emitapp::EmitApplet;
+use crate::flateapp::{DeflateApplet, InflateApplet};
+use crate::hexapp::{HexApplet, UnHexApplet};
+use crate::parselitapp::ParseLitApplet;
+use crate::pkcs7app::Pkcs7Applet;
+use crate::rc4app::Rc4Applet;
+use crate::teaapp::TeaApplet;
+use crate::urlapp::{UrlDecApplet, UrlEncApplet};
+use crate::varintapp::VarintApplet;
+use crate::xorapp::XorApplet;
+use anyhow::{bail, Result};
+use clap::{arg, value_parser, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const KEY: &str = "000102030405060708090a0b0c0d0e0f";
+
+/* Round-trip checks: name, encoder and its arguments, decoder and its
+ * arguments, generator of the inputs */
+type Check = (
+    &'static str,
+    fn() -> Box<dyn Applet>,
+    &'static [&'static str],
+    fn() -> Box<dyn Applet>,
+    &'static [&'static str],
+    fn(&mut Rng) -> Vec<u8>,
+);
+
+const CHECKS: &[Check] = &[
+    (
+        "hex",
+        HexApplet::new,
+        &[],
+        UnHexApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "urlenc",
+        UrlEncApplet::new,
+        &[],
+        UrlDecApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "b64",
+        B64EncApplet::new,
+        &[],
+        B64DecApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "b64 -u",
+        B64EncApplet::new,
+        &["-u"],
+        B64DecApplet::new,
+        &["-u"],
+        Rng::bytes,
+    ),
+    (
+        "b45",
+        B45EncApplet::new,
+        &[],
+        B45DecApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "deflate",
+        DeflateApplet::new,
+        &[],
+        InflateApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "xor",
+        XorApplet::new,
+        &["-x", "c0ffee"],
+        XorApplet::new,
+        &["-x", "c0ffee"],
+        Rng::bytes,
+    ),
+    (
+        "pkcs7",
+        Pkcs7Applet::new,
+        &[],
+        Pkcs7Applet::new,
+        &["-r"],
+        Rng::bytes,
+    ),
+    (
+        "rc4",
+        Rc4Applet::new,
+        &["-k", KEY],
+        Rc4Applet::new,
+        &["-k", KEY],
+        Rng::bytes,
+    ),
+    (
+        "aes",
+        AesApplet::new,
+        &["-k", KEY, "--iv", KEY],
+        AesApplet::new,
+        &["-d", "-k", KEY, "--iv", KEY],
+        Rng::bytes,
+    ),
+    (
+        "tea",
+        TeaApplet::new,
+        &["-k", KEY],
+        TeaApplet::new,
+        &["-d", "-k", KEY],
+        Rng::blocks,
+    ),
+    (
+        "delta",
+        DeltaApplet::new,
+        &[],
+        DeltaApplet::new,
+        &["-r"],
+        Rng::bytes,
+    ),
+    (
+        "emit -l c",
+        EmitApplet::new,
+        &["-l", "c"],
+        ParseLitApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "emit -l python",
+        EmitApplet::new,
+        &["-l", "python"],
+        ParseLitApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "emit -l rust",
+        EmitApplet::new,
+        &["-l", "rust"],
+        ParseLitApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "emit -l js",
+        EmitApplet::new,
+        &["-l", "js"],
+        ParseLitApplet::new,
+        &[],
+        Rng::bytes,
+    ),
+    (
+        "prefixlen",
+        PrefixLenApplet::new,
+        &["-s", "2", "-e", "big"],
+        DerefApplet::new,
+        &["-s", "2", "-e", "big"],
+        Rng::bytes,
+    ),
+    (
+        "varint",
+        VarintApplet::new,
+        &[],
+        VarintApplet::new,
+        &["-r"],
+        Rng::uint,
+    ),
+    (
+        "varint -s",
+        VarintApplet::new,
+        &["-s"],
+        VarintApplet::new,
+        &["-r", "-s"],
+        Rng::int,
+    ),
+];
+
+/* xorshift64, good enough to generate test inputs */
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        let len = (self.next() % 256) as usize;
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+
+    /* For block ciphers without padding */
+    fn blocks(&mut self) -> Vec<u8> {
+        let mut res = self.bytes();
+        res.truncate(res.len() - res.len() % 8);
+        res
+    }
+
+    /* Decimal integers of any magnitude, as output by "varint -r" */
+    fn uint(&mut self) -> Vec<u8> {
+        let v = self.next() >> (self.next() % 64);
+        format!("{}\n", v).into_bytes()
+    }
+
+    fn int(&mut self) -> Vec<u8> {
+        let v = (self.next() as i64) >> (self.next() % 64);
+        format!("{}\n", v).into_bytes()
+    }
+}
+
+pub struct SelfTestApplet {
+    seed: Option<u64>,
+    rounds: u64,
+}
+
+impl SelfTestApplet {
+    fn run_check(check: &Check, rng: &mut Rng, rounds: u64) -> Result<()> {
+        let (_, enc, enc_args, dec, dec_args, input) = check;
+        let enc = build_applet(enc().as_ref(), enc_args)?;
+        let dec = build_applet(dec().as_ref(), dec_args)?;
+        for _ in 0..rounds {
+            let val = input(rng);
+            let res = dec.process(enc.process(val.clone())?)?;
+            if res != val {
+                bail!("round-trip mismatch for input {}", hex::encode(val));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Applet for SelfTestApplet {
+    fn command(&self) -> &'static str {
+        "selftest"
+    }
+    fn description(&self) -> &'static str {
+        "run codec round-trip checks on random inputs"
+    }
+
+    fn returns_data(&self) -> bool {
+        false
+    }
+
+    fn arg_or_stdin(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-s --seed <N> "seed for the random inputs, to reproduce a failure")
+                    .value_parser(value_parser!(u64).range(1..)),
+            )
+            .arg(
+                arg!(-n --rounds <N> "number of random inputs per check")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .default_value("64"),
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            seed: None,
+            rounds: 64,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            seed: args.get_one::<u64>("seed").copied(),
+            rounds: *args.get_one::<u64>("rounds").unwrap(),
+        }))
+    }
+
+    fn process(&self, _val: Vec<u8>) -> Result<Vec<u8>> {
+        let seed = self.seed.unwrap_or_else(|| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            (now.as_nanos() as u64) | 1
+        });
+        println!("seed: {}", seed);
+        let mut rng = Rng(seed);
+        let mut failed = 0;
+        for check in CHECKS {
+            match Self::run_check(check, &mut rng, self.rounds) {
+                Ok(()) => println!("{}: ok", check.0),
+                Err(e) => {
+                    println!("{}: FAILED, {:#}", check.0, e);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            bail!("{} of {} checks failed", failed, CHECKS.len());
+        }
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["selftest", "--seed", "1234", "--rounds", "8"])
+            .assert()
+            .stdout(predicates::str::starts_with("seed: 1234\n"))
+            .stdout(predicates::str::contains("hex: ok"))
+            .stdout(predicates::str::contains("tea: ok"))
+            .stdout(predicates::str::contains("FAILED").not())
+            .success();
+    }
+
+    #[test]
+    fn test_checks() {
+        let mut rng = Rng(0x1234);
+        for check in CHECKS {
+            SelfTestApplet::run_check(check, &mut rng, 16).unwrap();
+        }
+    }
+}
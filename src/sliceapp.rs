@@ -139,9 +139,7 @@ impl SliceApplet {
 
         let start = self.start.offset;
 
-        if self.end.is_some() {
-            let end_pos = self.end.as_ref().unwrap();
-
+        if let Some(end_pos) = self.end.as_ref() {
             let end = if end_pos.relative {
                 start + end_pos.offset
             } else {
@@ -187,8 +185,7 @@ impl SliceApplet {
             .with_context(|| "seek failed")?;
 
         let mut res = vec![];
-        if self.end.is_some() {
-            let end_pos = self.end.as_ref().unwrap();
+        if let Some(end_pos) = self.end.as_ref() {
             let end = if end_pos.from_end {
                 flen - end_pos.offset
             } else if end_pos.relative {
@@ -224,7 +221,7 @@ mod tests {
         thread_rng().fill(&mut rand_data[..]);
 
         let mut tmpfile = tempfile::NamedTempFile::new().unwrap();
-        tmpfile.write(&rand_data).unwrap();
+        tmpfile.write_all(&rand_data).unwrap();
         (tmpfile, rand_data)
     }
 
@@ -311,23 +308,23 @@ mod tests {
     #[test]
     fn test_cli_file() {
         let mut data: [u8; 10] = [0; 10];
-        for i in (0..10).into_iter() {
-            data[i] = i as u8;
+        for (i, d) in data.iter_mut().enumerate() {
+            *d = i as u8;
         }
 
         let mut tmpfile = tempfile::NamedTempFile::new().unwrap();
-        tmpfile.write(&data).unwrap();
+        tmpfile.write_all(&data).unwrap();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", &tmpfile.path().to_str().unwrap(), "2", "+0x3"])
+            .args(["slice", tmpfile.path().to_str().unwrap(), "2", "+0x3"])
             .assert()
             .stdout(&b"\x02\x03\x04"[..])
             .success();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", &tmpfile.path().to_str().unwrap(), "2"])
+            .args(["slice", tmpfile.path().to_str().unwrap(), "2"])
             .assert()
             .stdout(&b"\x02\x03\x04\x05\x06\x07\x08\x09"[..])
             .success();
@@ -335,30 +332,30 @@ mod tests {
         /* Should fail because "start" is before beginning of file */
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", &tmpfile.path().to_str().unwrap(), "-200"])
+            .args(["slice", tmpfile.path().to_str().unwrap(), "-200"])
             .assert()
             .failure();
 
         /* Should fail because "end" is before "start */
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", &tmpfile.path().to_str().unwrap(), "0", "-300"])
+            .args(["slice", tmpfile.path().to_str().unwrap(), "0", "-300"])
             .assert()
             .failure();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", "--", &tmpfile.path().to_str().unwrap(), "-2"])
+            .args(["slice", "--", tmpfile.path().to_str().unwrap(), "-2"])
             .assert()
             .stdout(&b"\x08\x09"[..])
             .success();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&[
+            .args([
                 "slice",
                 "--",
-                &tmpfile.path().to_str().unwrap(),
+                tmpfile.path().to_str().unwrap(),
                 "-0x2",
                 "+1",
             ])
@@ -370,22 +367,22 @@ mod tests {
     #[test]
     fn test_cli_stdin() {
         let mut data: [u8; 10] = [0; 10];
-        for i in (0..10).into_iter() {
-            data[i] = i as u8;
+        for (i, d) in data.iter_mut().enumerate() {
+            *d = i as u8;
         }
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", "-", "2", "+3"])
-            .write_stdin(*&data)
+            .args(["slice", "-", "2", "+3"])
+            .write_stdin(data)
             .assert()
             .stdout(&b"\x02\x03\x04"[..])
             .success();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", "-", "2"])
-            .write_stdin(*&data)
+            .args(["slice", "-", "2"])
+            .write_stdin(data)
             .assert()
             .stdout(&b"\x02\x03\x04\x05\x06\x07\x08\x09"[..])
             .success();
@@ -393,8 +390,8 @@ mod tests {
         /* Should fail because stdin is not seekable */
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", "-", "-2"])
-            .write_stdin(*&data)
+            .args(["slice", "-", "-2"])
+            .write_stdin(data)
             .assert()
             .stdout("")
             .failure();
@@ -402,16 +399,16 @@ mod tests {
         /* Should fail because stdin is not seekable */
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", "-", "0", "-10"])
-            .write_stdin(*&data)
+            .args(["slice", "-", "0", "-10"])
+            .write_stdin(data)
             .assert()
             .stdout("")
             .failure();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["slice", "-", "0", "0"])
-            .write_stdin(*&data)
+            .args(["slice", "-", "0", "0"])
+            .write_stdin(data)
             .assert()
             .stdout(&b""[..])
             .success();
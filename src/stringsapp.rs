@@ -0,0 +1,176 @@
+use crate::applet::Applet;
+use anyhow::Result;
+use clap::{arg, value_parser, Command};
+
+#[derive(clap::ValueEnum, Clone, Default, Debug)]
+enum StrEncoding {
+    #[default]
+    Ascii,
+    Utf16le,
+}
+
+pub struct StringsApplet {
+    min_len: usize,
+    encoding: StrEncoding,
+    offset: bool,
+}
+
+/* Same definition as GNU strings: printable ASCII, plus tab */
+fn is_printable(c: u8) -> bool {
+    c.is_ascii_graphic() || c == b' ' || c == b'\t'
+}
+
+/* Returns a list of (offset, string) */
+fn ascii_strings(val: &[u8], min_len: usize) -> Vec<(usize, &[u8])> {
+    let mut res = Vec::new();
+    let mut start = 0;
+    for (i, c) in val.iter().enumerate() {
+        if !is_printable(*c) {
+            if i - start >= min_len {
+                res.push((start, &val[start..i]));
+            }
+            start = i + 1;
+        }
+    }
+    if val.len() - start >= min_len {
+        res.push((start, &val[start..]));
+    }
+    res
+}
+
+/* UTF-16LE "wide" strings: printable ASCII char followed by a NUL byte.
+ * Returns a list of (offset, string with NUL bytes removed) */
+fn utf16le_strings(val: &[u8], min_len: usize) -> Vec<(usize, Vec<u8>)> {
+    let mut res = Vec::new();
+    // Try both alignments, as strings can start at odd offsets
+    for align in 0..2 {
+        let mut start = align;
+        let mut cur = Vec::new();
+        let mut i = align;
+        while i + 1 < val.len() {
+            if is_printable(val[i]) && val[i + 1] == 0 {
+                cur.push(val[i]);
+            } else {
+                if cur.len() >= min_len {
+                    res.push((start, cur.clone()));
+                }
+                cur.clear();
+                start = i + 2;
+            }
+            i += 2;
+        }
+        if cur.len() >= min_len {
+            res.push((start, cur));
+        }
+    }
+    res.sort_by_key(|(off, _)| *off);
+    res
+}
+
+impl Applet for StringsApplet {
+    fn command(&self) -> &'static str {
+        "strings"
+    }
+    fn description(&self) -> &'static str {
+        "extract printable strings"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-n --min <N> "minimum string length")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .default_value("4"),
+            )
+            .arg(
+                arg!(-e --encoding <encoding> "character encoding of strings")
+                    .value_parser(clap::builder::EnumValueParser::<StrEncoding>::new())
+                    .default_value("ascii"),
+            )
+            .arg(arg!(-o --offset "prefix each string with its offset (in hex)"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            min_len: 4,
+            encoding: StrEncoding::Ascii,
+            offset: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            min_len: *args.get_one::<u64>("min").unwrap() as usize,
+            encoding: args.get_one::<StrEncoding>("encoding").unwrap().clone(),
+            offset: args.get_flag("offset"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let found: Vec<(usize, Vec<u8>)> = match self.encoding {
+            StrEncoding::Ascii => ascii_strings(&val, self.min_len)
+                .into_iter()
+                .map(|(o, s)| (o, s.to_vec()))
+                .collect(),
+            StrEncoding::Utf16le => utf16le_strings(&val, self.min_len),
+        };
+        let mut res = Vec::new();
+        for (off, s) in found {
+            if self.offset {
+                res.extend_from_slice(format!("0x{:x} ", off).as_bytes());
+            }
+            res.extend(s);
+            res.push(b'\n');
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_offset() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["strings", "-o"])
+            .write_stdin(&b"\x00\x01test\xffab\x00longer string"[..])
+            .assert()
+            .stdout("0x2 test\n0xa longer string\n")
+            .success();
+    }
+
+    #[test]
+    fn test_ascii() {
+        let strings = StringsApplet {
+            min_len: 3,
+            encoding: StrEncoding::Ascii,
+            offset: false,
+        };
+        assert_eq!(
+            strings.process_test(b"ab\x00abc\x01\x02hello world\tok\x7f".to_vec()),
+            b"abc\nhello world\tok\n"
+        );
+        assert_eq!(strings.process_test(b"\x00\x01\x02".to_vec()), b"");
+    }
+
+    #[test]
+    fn test_utf16le() {
+        let strings = StringsApplet {
+            min_len: 4,
+            encoding: StrEncoding::Utf16le,
+            offset: true,
+        };
+        assert_eq!(
+            strings.process_test(b"\xffK\x00e\x00r\x00n\x00e\x00l\x00\x00\x00A\x00B\x00".to_vec()),
+            b"0x1 Kernel\n"
+        );
+        assert_eq!(
+            strings.process_test(b"t\x00e\x00s\x00t\x00".to_vec()),
+            b"0x0 test\n"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::applet::Applet;
+use anyhow::Result;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+pub struct StripAnsiApplet {}
+
+impl StripAnsiApplet {
+    /* Returns the length of the escape sequence at the start of val,
+     * or None if it is not a complete, valid sequence */
+    fn sequence_len(val: &[u8]) -> Option<usize> {
+        match val.get(1)? {
+            // CSI: parameters (0x30-0x3f), intermediates (0x20-0x2f), final byte (0x40-0x7e)
+            b'[' => {
+                let params = val[2..]
+                    .iter()
+                    .take_while(|c| (0x30..=0x3f).contains(*c))
+                    .count();
+                let inter = val[2 + params..]
+                    .iter()
+                    .take_while(|c| (0x20..=0x2f).contains(*c))
+                    .count();
+                let end = 2 + params + inter;
+                (0x40..=0x7e).contains(val.get(end)?).then_some(end + 1)
+            }
+            // OSC (window title, hyperlinks): terminated by BEL or ESC '\'
+            b']' => {
+                let mut i = 2;
+                loop {
+                    match *val.get(i)? {
+                        BEL => return Some(i + 1),
+                        ESC if val.get(i + 1) == Some(&b'\\') => return Some(i + 2),
+                        ESC => return None,
+                        _ => i += 1,
+                    }
+                }
+            }
+            // other sequences: intermediates, then a final byte (ex: ESC ( B)
+            _ => {
+                let inter = val[1..]
+                    .iter()
+                    .take_while(|c| (0x20..=0x2f).contains(*c))
+                    .count();
+                (0x30..=0x7e)
+                    .contains(val.get(1 + inter)?)
+                    .then_some(inter + 2)
+            }
+        }
+    }
+}
+
+impl Applet for StripAnsiApplet {
+    fn command(&self) -> &'static str {
+        "stripansi"
+    }
+    fn description(&self) -> &'static str {
+        "remove ANSI escape sequences"
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {})
+    }
+
+    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {}))
+    }
+
+    /* Incomplete or invalid sequences are kept as is */
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let mut res = Vec::with_capacity(val.len());
+        let mut i = 0;
+        while i < val.len() {
+            if val[i] == ESC {
+                if let Some(len) = Self::sequence_len(&val[i..]) {
+                    i += len;
+                    continue;
+                }
+            }
+            res.push(val[i]);
+            i += 1;
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["stripansi"])
+            .write_stdin("\x1b[1;31merror\x1b[0m: file \x1b[4mfoo\x1b[24m not found\n")
+            .assert()
+            .stdout("error: file foo not found\n")
+            .success();
+    }
+
+    #[test]
+    fn test_strip() {
+        let strip = StripAnsiApplet {};
+        assert_eq!(
+            strip.process_test(b"a\x1b[38;5;196mb\x1b[mc\x1b[2Kd\x1b[?25le".to_vec()),
+            b"abcde"
+        );
+        assert_eq!(
+            strip.process_test(b"\x1b]0;title\x07text\x1b]8;;http://x\x1b\\link".to_vec()),
+            b"textlink"
+        );
+        assert_eq!(strip.process_test(b"\x1b(Bx\x1b7y\x1b8".to_vec()), b"xy");
+        // incomplete or invalid sequences do not consume the following text
+        assert_eq!(strip.process_test(b"a\x1b[12".to_vec()), b"a\x1b[12");
+        assert_eq!(strip.process_test(b"a\x1b[1\nb".to_vec()), b"a\x1b[1\nb");
+        assert_eq!(strip.process_test(b"\x1b".to_vec()), b"\x1b");
+        assert_eq!(
+            strip.process_test(b"\x1b]0;title\n".to_vec()),
+            b"\x1b]0;title\n"
+        );
+        assert_eq!(strip.process_test(b"no escape".to_vec()), b"no escape");
+    }
+}
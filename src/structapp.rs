@@ -0,0 +1,365 @@
+use crate::applet::{Applet, Endianness};
+use anyhow::{bail, Context, Result};
+use clap::{arg, value_parser, Command};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FieldType {
+    Uint(usize, Endianness),
+    Int(usize, Endianness),
+    Float(usize, Endianness),
+    Bytes(usize),
+}
+
+impl FieldType {
+    /* "u16le", "i32be", "f64", "bytes:4", etc. Multi-byte types default to
+     * little endian */
+    fn parse(spec: &str) -> Result<Self> {
+        if let Some(len) = spec.strip_prefix("bytes:") {
+            let len = len
+                .parse::<usize>()
+                .with_context(|| format!("Invalid bytes length \"{}\"", len))?;
+            return Ok(FieldType::Bytes(len));
+        }
+        let (base, endian) = if let Some(b) = spec.strip_suffix("le") {
+            (b, Endianness::Little)
+        } else if let Some(b) = spec.strip_suffix("be") {
+            (b, Endianness::Big)
+        } else {
+            (spec, Endianness::Little)
+        };
+        Ok(match base {
+            "u8" | "u16" | "u32" | "u64" => {
+                FieldType::Uint(base[1..].parse::<usize>()? / 8, endian)
+            }
+            "i8" | "i16" | "i32" | "i64" => FieldType::Int(base[1..].parse::<usize>()? / 8, endian),
+            "f32" | "f64" => FieldType::Float(base[1..].parse::<usize>()? / 8, endian),
+            _ => bail!("Unknown field type \"{}\"", spec),
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            FieldType::Uint(s, _)
+            | FieldType::Int(s, _)
+            | FieldType::Float(s, _)
+            | FieldType::Bytes(s) => *s,
+        }
+    }
+
+    fn format(&self, data: &[u8]) -> String {
+        match self {
+            FieldType::Uint(s, e) => {
+                let v = e.read_uint(data);
+                format!("{} (0x{:0width$x})", v, v, width = s * 2)
+            }
+            FieldType::Int(s, e) => {
+                /* Sign extend */
+                let shift = 64 - s * 8;
+                format!("{}", ((e.read_uint(data) << shift) as i64) >> shift)
+            }
+            FieldType::Float(4, e) => format!("{:?}", f32::from_bits(e.read_uint(data) as u32)),
+            FieldType::Float(_, e) => format!("{:?}", f64::from_bits(e.read_uint(data))),
+            FieldType::Bytes(_) => hex::encode(data),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Field {
+    name: String,
+    spec: String,
+    ftype: FieldType,
+}
+
+/* Comma separated list of fields, each with an optional "name=" prefix */
+fn parse_fields(spec: &str) -> Result<Vec<Field>> {
+    spec.split(',')
+        .map(str::trim)
+        .enumerate()
+        .map(|(i, f)| {
+            let (name, ftype) = match f.split_once('=') {
+                Some((n, t)) => (n.to_string(), t),
+                None => (format!("{}", i), f),
+            };
+            Ok(Field {
+                name,
+                spec: ftype.to_string(),
+                ftype: FieldType::parse(&ftype.to_lowercase())?,
+            })
+        })
+        .collect()
+}
+
+/* Size of the structure, checking for overflows of huge bytes:N fields */
+fn struct_size(fields: &[Field]) -> Result<usize> {
+    let size = fields
+        .iter()
+        .try_fold(0usize, |size, f| size.checked_add(f.ftype.size()))
+        .context("Structure size overflows")?;
+    if size == 0 {
+        bail!("Structure size is 0");
+    }
+    Ok(size)
+}
+
+pub struct StructApplet {
+    fields: Vec<Field>,
+    count: usize,
+}
+
+impl Applet for StructApplet {
+    fn command(&self) -> &'static str {
+        "struct"
+    }
+    fn description(&self) -> &'static str {
+        "parse packed structures and print their fields"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-f --fields <SPEC> "comma separated field types, optionally named: magic=u32le,u8,bytes:4")
+                    .required(true),
+            )
+            .arg(
+                arg!(-c --count <N> "number of consecutive structures to parse, 0 for as many as possible")
+                    .value_parser(value_parser!(u64))
+                    .default_value("1"),
+            )
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Field types: u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, with an optional \
+                 le/be suffix (little endian by default), and bytes:N.\n\
+                 Data after the last structure is ignored.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            fields: vec![],
+            count: 1,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let fields = parse_fields(args.get_one::<String>("fields").unwrap())?;
+        struct_size(&fields)?;
+        Ok(Box::new(Self {
+            fields,
+            count: *args.get_one::<u64>("count").unwrap() as usize,
+        }))
+    }
+
+    fn explain(&self) -> String {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|f| format!("{}={}", f.name, f.spec))
+            .collect();
+        format!(
+            "struct: parse {} structures of {} bytes, with fields {}.",
+            match self.count {
+                0 => "as many".to_string(),
+                c => c.to_string(),
+            },
+            struct_size(&self.fields).unwrap_or(0),
+            fields.join(", ")
+        )
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let size = struct_size(&self.fields)?;
+        let count = match self.count {
+            0 => val.len() / size,
+            c => c,
+        };
+        match count.checked_mul(size) {
+            Some(total) if total <= val.len() => (),
+            total => bail!(
+                "Input is {} bytes long, {} structures of {} bytes need {}",
+                val.len(),
+                count,
+                size,
+                total.map_or("more".to_string(), |t| t.to_string())
+            ),
+        }
+
+        /* Rows of offset, name, type, value */
+        let mut rows = vec![];
+        let mut off = 0;
+        for i in 0..count {
+            for f in self.fields.iter() {
+                let name = match count {
+                    1 => f.name.clone(),
+                    _ => format!("[{}].{}", i, f.name),
+                };
+                let data = &val[off..off + f.ftype.size()];
+                rows.push([
+                    format!("0x{:x}", off),
+                    name,
+                    f.spec.clone(),
+                    f.ftype.format(data),
+                ]);
+                off += f.ftype.size();
+            }
+        }
+
+        let mut widths = [0; 3];
+        for r in rows.iter() {
+            for (w, c) in widths.iter_mut().zip(r.iter()) {
+                *w = (*w).max(c.len());
+            }
+        }
+        let mut res = String::new();
+        for r in rows {
+            res.push_str(&format!(
+                "{:<w0$}  {:<w1$}  {:<w2$}  {}\n",
+                r[0],
+                r[1],
+                r[2],
+                r[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            ));
+        }
+        Ok(res.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::applet::build_applet;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "struct",
+                "-f",
+                "magic=bytes:4,class=u8,data=u8,version=u8,pad=bytes:9,type=u16le",
+            ])
+            .write_stdin(
+                b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x00".as_slice(),
+            )
+            .assert()
+            .stdout(
+                "0x0   magic    bytes:4  7f454c46\n\
+                 0x4   class    u8       2 (0x02)\n\
+                 0x5   data     u8       1 (0x01)\n\
+                 0x6   version  u8       1 (0x01)\n\
+                 0x7   pad      bytes:9  000000000000000000\n\
+                 0x10  type     u16le    3 (0x0003)\n",
+            )
+            .success();
+    }
+
+    #[test]
+    fn test_overflow_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args([
+                "struct",
+                "-f",
+                "u64",
+                "-c",
+                "18446744073709551615",
+                "abcdefgh",
+            ])
+            .assert()
+            .stderr(predicates::str::contains("structures of 8 bytes need more"))
+            .failure();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["struct", "-f", "bytes:18446744073709551615,u8", "abcd"])
+            .assert()
+            .stderr(predicates::str::contains("Structure size overflows"))
+            .failure();
+    }
+
+    #[test]
+    fn test_fields() {
+        let st = StructApplet {
+            fields: parse_fields("u16be,i8,i32,f32le").unwrap(),
+            count: 1,
+        };
+        assert_eq!(
+            String::from_utf8(
+                st.process_test(b"\x12\x34\xff\xfe\xff\xff\xff\x00\x00\x80\x3f".to_vec())
+            )
+            .unwrap(),
+            "0x0  0  u16be  4660 (0x1234)\n\
+             0x2  1  i8     -1\n\
+             0x3  2  i32    -2\n\
+             0x7  3  f32le  1.0\n"
+        );
+
+        assert_eq!(
+            parse_fields("x=U64BE").unwrap()[0].ftype,
+            FieldType::Uint(8, Endianness::Big)
+        );
+        assert!(parse_fields("u24").is_err());
+        assert!(parse_fields("bytes:x").is_err());
+        assert!(parse_fields("u8,").is_err());
+
+        // overflowing structure size
+        let fields = parse_fields("bytes:18446744073709551615,u8").unwrap();
+        assert!(struct_size(&fields).is_err());
+        let st = StructApplet { fields, count: 1 };
+        assert!(st.process(b"abcd".to_vec()).is_err());
+        assert!(struct_size(&parse_fields("bytes:0").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_count() {
+        let st = StructApplet {
+            fields: parse_fields("id=u8,len=u16le").unwrap(),
+            count: 2,
+        };
+        assert_eq!(
+            String::from_utf8(st.process_test(b"\x01\x10\x00\x02\x20\x00\xff".to_vec())).unwrap(),
+            "0x0  [0].id   u8     1 (0x01)\n\
+             0x1  [0].len  u16le  16 (0x0010)\n\
+             0x3  [1].id   u8     2 (0x02)\n\
+             0x4  [1].len  u16le  32 (0x0020)\n"
+        );
+        // truncated input
+        assert!(st.process(b"\x01\x10\x00\x02".to_vec()).is_err());
+
+        // overflowing count
+        let st = StructApplet {
+            fields: parse_fields("u64").unwrap(),
+            count: usize::MAX,
+        };
+        assert!(st.process(b"abcdefgh".to_vec()).is_err());
+
+        // as many as possible
+        let st = StructApplet {
+            fields: parse_fields("u16").unwrap(),
+            count: 0,
+        };
+        assert_eq!(
+            st.process_test(b"\x01\x00\x02\x00\x03".to_vec())
+                .split(|c| *c == b'\n')
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let st = build_applet(
+            StructApplet::new().as_ref(),
+            &["-f", "magic=u32le,u8", "-c", "0"],
+        )
+        .unwrap();
+        assert_eq!(
+            st.explain(),
+            "struct: parse as many structures of 5 bytes, with fields magic=u32le, 1=u8."
+        );
+    }
+}
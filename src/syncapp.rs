@@ -0,0 +1,106 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+pub struct SyncApplet {
+    marker: Vec<u8>,
+    after: bool,
+    strict: bool,
+}
+
+impl Applet for SyncApplet {
+    fn command(&self) -> &'static str {
+        "sync"
+    }
+    fn description(&self) -> &'static str {
+        "discard input until a marker"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-m --marker <HEX> "marker to look for, in hex").required(true))
+            .arg(arg!(-a --after "output data after the marker, instead of from the marker"))
+            .arg(arg!(-s --strict "fail if the marker is not found, instead of outputting nothing"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            marker: vec![],
+            after: false,
+            strict: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let marker_hex = args.get_one::<String>("marker").unwrap();
+        let marker = hex::decode(marker_hex.replace(' ', ""))
+            .with_context(|| format!("Invalid hex marker \"{}\"", marker_hex))?;
+        if marker.is_empty() {
+            bail!("Empty marker");
+        }
+        Ok(Box::new(Self {
+            marker,
+            after: args.get_flag("after"),
+            strict: args.get_flag("strict"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        match val
+            .windows(self.marker.len())
+            .position(|w| w == self.marker.as_slice())
+        {
+            Some(pos) if self.after => Ok(val[pos + self.marker.len()..].to_vec()),
+            Some(pos) => Ok(val[pos..].to_vec()),
+            None if self.strict => bail!("Marker not found"),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["sync", "-m", "cafe"])
+            .write_stdin(b"\x00\x01\xca\xfe\xca\xfe\x02".as_slice())
+            .assert()
+            .stdout(predicates::ord::eq(b"\xca\xfe\xca\xfe\x02".as_slice()))
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["sync", "-m", "cafe", "--strict", "abcd"])
+            .assert()
+            .stderr(predicates::str::contains("Marker not found"))
+            .failure();
+    }
+
+    #[test]
+    fn test_sync() {
+        let sync = |marker: &[u8], after: bool, strict: bool, val: &[u8]| {
+            let app = SyncApplet {
+                marker: marker.to_vec(),
+                after,
+                strict,
+            };
+            app.process(val.to_vec())
+        };
+        assert_eq!(sync(b"MZ", false, false, b"junkMZ\x90").unwrap(), b"MZ\x90");
+        assert_eq!(sync(b"MZ", true, false, b"junkMZ\x90").unwrap(), b"\x90");
+        assert_eq!(sync(b"MZ", true, true, b"MZ").unwrap(), b"");
+        // first occurrence only
+        assert_eq!(sync(b"ab", false, false, b"xabxab").unwrap(), b"abxab");
+
+        // absent marker, marker longer than the input
+        assert_eq!(sync(b"MZ", false, false, b"junk").unwrap(), b"");
+        assert!(sync(b"MZ", false, true, b"junk").is_err());
+        assert!(sync(b"MZ", true, true, b"M").is_err());
+    }
+}
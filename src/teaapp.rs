@@ -0,0 +1,263 @@
+use crate::applet::{Applet, Endianness};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+const DELTA: u32 = 0x9e3779b9;
+const CYCLES: u32 = 32;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum TeaVariant {
+    Tea,
+    Xtea,
+}
+
+pub struct TeaApplet {
+    variant: TeaVariant,
+    key: [u32; 4],
+    endian: Endianness,
+    decrypt: bool,
+}
+
+impl TeaApplet {
+    fn crypt_block(&self, v: [u32; 2]) -> [u32; 2] {
+        let k = &self.key;
+        let [mut v0, mut v1] = v;
+        match (self.variant, self.decrypt) {
+            (TeaVariant::Tea, false) => {
+                let mut sum: u32 = 0;
+                for _ in 0..CYCLES {
+                    sum = sum.wrapping_add(DELTA);
+                    v0 = v0.wrapping_add(
+                        (v1 << 4).wrapping_add(k[0])
+                            ^ v1.wrapping_add(sum)
+                            ^ (v1 >> 5).wrapping_add(k[1]),
+                    );
+                    v1 = v1.wrapping_add(
+                        (v0 << 4).wrapping_add(k[2])
+                            ^ v0.wrapping_add(sum)
+                            ^ (v0 >> 5).wrapping_add(k[3]),
+                    );
+                }
+            }
+            (TeaVariant::Tea, true) => {
+                let mut sum = DELTA.wrapping_mul(CYCLES);
+                for _ in 0..CYCLES {
+                    v1 = v1.wrapping_sub(
+                        (v0 << 4).wrapping_add(k[2])
+                            ^ v0.wrapping_add(sum)
+                            ^ (v0 >> 5).wrapping_add(k[3]),
+                    );
+                    v0 = v0.wrapping_sub(
+                        (v1 << 4).wrapping_add(k[0])
+                            ^ v1.wrapping_add(sum)
+                            ^ (v1 >> 5).wrapping_add(k[1]),
+                    );
+                    sum = sum.wrapping_sub(DELTA);
+                }
+            }
+            (TeaVariant::Xtea, false) => {
+                let mut sum: u32 = 0;
+                for _ in 0..CYCLES {
+                    v0 = v0.wrapping_add(
+                        ((v1 << 4) ^ (v1 >> 5)).wrapping_add(v1)
+                            ^ sum.wrapping_add(k[(sum & 3) as usize]),
+                    );
+                    sum = sum.wrapping_add(DELTA);
+                    v1 = v1.wrapping_add(
+                        ((v0 << 4) ^ (v0 >> 5)).wrapping_add(v0)
+                            ^ sum.wrapping_add(k[((sum >> 11) & 3) as usize]),
+                    );
+                }
+            }
+            (TeaVariant::Xtea, true) => {
+                let mut sum = DELTA.wrapping_mul(CYCLES);
+                for _ in 0..CYCLES {
+                    v1 = v1.wrapping_sub(
+                        ((v0 << 4) ^ (v0 >> 5)).wrapping_add(v0)
+                            ^ sum.wrapping_add(k[((sum >> 11) & 3) as usize]),
+                    );
+                    sum = sum.wrapping_sub(DELTA);
+                    v0 = v0.wrapping_sub(
+                        ((v1 << 4) ^ (v1 >> 5)).wrapping_add(v1)
+                            ^ sum.wrapping_add(k[(sum & 3) as usize]),
+                    );
+                }
+            }
+        }
+        [v0, v1]
+    }
+}
+
+impl Applet for TeaApplet {
+    fn command(&self) -> &'static str {
+        "tea"
+    }
+    fn description(&self) -> &'static str {
+        "TEA/XTEA encryption/decryption"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-a --variant <variant> "cipher variant")
+                    .value_parser(clap::builder::EnumValueParser::<TeaVariant>::new())
+                    .default_value("xtea"),
+            )
+            .arg(arg!(-k --key <HEX> "128-bit key, in hex").required(true))
+            .arg(
+                arg!(-e --endian <endian> "endianness of the 32-bit words of blocks and key")
+                    .value_parser(clap::builder::EnumValueParser::<Endianness>::new())
+                    .default_value("big"),
+            )
+            .arg(arg!(-d --decrypt "decrypt instead of encrypting"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Blocks are processed independently (ECB), the input length must be a multiple \
+                 of 8 bytes.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            variant: TeaVariant::Xtea,
+            key: [0; 4],
+            endian: Endianness::Big,
+            decrypt: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let endian = *args.get_one::<Endianness>("endian").unwrap();
+        let key_bytes =
+            hex::decode(args.get_one::<String>("key").unwrap()).context("Invalid hex in key")?;
+        if key_bytes.len() != 16 {
+            bail!("Invalid key length {}, must be 16 bytes", key_bytes.len());
+        }
+        let mut key = [0; 4];
+        for (k, word) in key.iter_mut().zip(key_bytes.chunks_exact(4)) {
+            *k = endian.read_uint(word) as u32;
+        }
+        Ok(Box::new(Self {
+            variant: *args.get_one::<TeaVariant>("variant").unwrap(),
+            key,
+            endian,
+            decrypt: args.get_flag("decrypt"),
+        }))
+    }
+
+    fn explain(&self) -> String {
+        format!(
+            "tea: {} 8 bytes blocks with {}, 128-bit key, {} endian words.",
+            if self.decrypt { "decrypt" } else { "encrypt" },
+            match self.variant {
+                TeaVariant::Tea => "TEA",
+                TeaVariant::Xtea => "XTEA",
+            },
+            match self.endian {
+                Endianness::Little => "little",
+                Endianness::Big => "big",
+            }
+        )
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if val.len() % 8 != 0 {
+            bail!("Input length {} is not a multiple of 8 bytes", val.len());
+        }
+        let mut res = Vec::with_capacity(val.len());
+        for block in val.chunks_exact(8) {
+            let v = [
+                self.endian.read_uint(&block[..4]) as u32,
+                self.endian.read_uint(&block[4..]) as u32,
+            ];
+            for word in self.crypt_block(v) {
+                res.extend(self.endian.write_uint(word as u64, 4).unwrap());
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::applet::build_applet;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["tea", "-k", "000102030405060708090a0b0c0d0e0f", "ABCDEFGH"])
+            .assert()
+            .stdout(predicates::ord::eq(
+                hex::decode("497df3d072612cb5").unwrap(),
+            ))
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["tea", "-k", "00", "ABCDEFGH"])
+            .assert()
+            .stderr(predicates::str::contains("Invalid key length"))
+            .failure();
+    }
+
+    #[test]
+    fn test_vectors() {
+        let tea = TeaApplet {
+            variant: TeaVariant::Tea,
+            key: [0; 4],
+            endian: Endianness::Big,
+            decrypt: false,
+        };
+        assert_eq!(
+            hex::encode(tea.process_test(vec![0; 8])),
+            "41ea3a0a94baa940"
+        );
+
+        let xtea = TeaApplet {
+            variant: TeaVariant::Xtea,
+            key: [0; 4],
+            endian: Endianness::Big,
+            decrypt: false,
+        };
+        assert_eq!(
+            hex::encode(xtea.process_test(vec![0; 8])),
+            "dee9d4d8f7131ed9"
+        );
+        assert!(xtea.process(vec![0; 7]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let plain = b"Lightweight ciphr".repeat(8)[..128].to_vec();
+        for variant in [TeaVariant::Tea, TeaVariant::Xtea] {
+            for endian in [Endianness::Big, Endianness::Little] {
+                let mut tea = TeaApplet {
+                    variant,
+                    key: [0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210],
+                    endian,
+                    decrypt: false,
+                };
+                let enc = tea.process_test(plain.clone());
+                assert_ne!(enc, plain);
+                tea.decrypt = true;
+                assert_eq!(tea.process_test(enc), plain);
+            }
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let tea = build_applet(
+            TeaApplet::new().as_ref(),
+            &["-a", "xtea", "-e", "little", "-d", "-k", &"00".repeat(16)],
+        )
+        .unwrap();
+        assert_eq!(
+            tea.explain(),
+            "tea: decrypt 8 bytes blocks with XTEA, 128-bit key, little endian words."
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use crate::applet::{Applet, FromStrWithRadix};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{arg, Command};
 use std::convert::TryFrom;
-use time::{format_description, Duration, OffsetDateTime, UtcOffset};
+use time::{
+    format_description, Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+};
 
 /*
     Decode a numeric timestamp in Epoch seconds format to a human-readable timestamp.
@@ -43,15 +45,76 @@ fn decode_epoch_subseconds(ts: i64, resolution: i64) -> Result<OffsetDateTime> {
       2025: 133801632000000000
       2065: 146424672000000000
 */
+const FILETIME_EPOCH_OFFSET: i64 = 116_444_736_000_000_000;
+
 fn decode_windows_filetime(ts: i64) -> Result<OffsetDateTime> {
     /* Shift to Unix Epoch */
-    let shifted = ts - 116_444_736_000_000_000;
+    let shifted = ts
+        .checked_sub(FILETIME_EPOCH_OFFSET)
+        .with_context(|| format!("Out of range FILETIME {}", ts))?;
     decode_epoch_subseconds(shifted, 10_000_000)
 }
 
+fn encode_windows_filetime(date: OffsetDateTime) -> i64 {
+    /* Sub-100ns precision is truncated, toward the past */
+    let intervals = date.unix_timestamp_nanos().div_euclid(100);
+    intervals as i64 + FILETIME_EPOCH_OFFSET
+}
+
+/*
+    DOS date and time, as found in FAT directory entries and ZIP headers: two 16-bit
+    fields, given here as a 32-bit value with the date in the high word.
+
+    date: bits 15-9: year - 1980, bits 8-5: month, bits 4-0: day
+    time: bits 15-11: hours, bits 10-5: minutes, bits 4-0: seconds / 2
+
+    DOS timestamps are in local time, without time zone.
+*/
+fn decode_dos_datetime(ts: u32) -> Result<PrimitiveDateTime> {
+    let (date, time) = (ts >> 16, ts & 0xffff);
+    let month = Month::try_from(((date >> 5) & 0xf) as u8).context("Invalid DOS month")?;
+    let date = Date::from_calendar_date(1980 + (date >> 9) as i32, month, (date & 0x1f) as u8)
+        .context("Invalid DOS date")?;
+    let time = Time::from_hms(
+        (time >> 11) as u8,
+        ((time >> 5) & 0x3f) as u8,
+        (time & 0x1f) as u8 * 2,
+    )
+    .context("Invalid DOS time")?;
+    Ok(PrimitiveDateTime::new(date, time))
+}
+
+fn encode_dos_datetime(dt: PrimitiveDateTime) -> Result<u32> {
+    if !(1980..=2107).contains(&dt.year()) {
+        bail!("DOS dates range from 1980 to 2107");
+    }
+    /* Odd seconds are truncated, DOS has a 2 seconds resolution */
+    let date = (((dt.year() - 1980) as u32) << 9) | ((dt.month() as u32) << 5) | dt.day() as u32;
+    let time = ((dt.hour() as u32) << 11) | ((dt.minute() as u32) << 5) | (dt.second() as u32 / 2);
+    Ok((date << 16) | time)
+}
+
+/* Render a duration in seconds as "3d 4h 12m 5s", skipping null components */
+fn format_duration(secs: i64) -> String {
+    let sign = if secs < 0 { "-" } else { "" };
+    let mut rem = secs.unsigned_abs();
+    let mut parts = Vec::new();
+    for (unit, len) in [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)] {
+        if rem >= len {
+            parts.push(format!("{}{}", rem / len, unit));
+            rem %= len;
+        }
+    }
+    if parts.is_empty() {
+        return "0s".to_string();
+    }
+    format!("{}{}", sign, parts.join(" "))
+}
+
 pub struct TimeApplet {
     local: bool,
     verbose: bool,
+    delta_to: Option<i64>,
 }
 impl Applet for TimeApplet {
     fn command(&self) -> &'static str {
@@ -64,8 +127,16 @@ impl Applet for TimeApplet {
     fn clap_command(&self) -> Command {
         Command::new(self.command())
             .about(self.description())
-            .arg(arg!(-l --local  "show time in local time zone"))
-            .arg(arg!(-v --verbose "show which type of timestamp was used for decoding"))
+            .arg(arg!(-l --local  "show time in local time zone").conflicts_with("delta"))
+            .arg(
+                arg!(-v --verbose "show which type of timestamp was used for decoding")
+                    .conflicts_with("delta"),
+            )
+            .arg(
+                arg!(-d --delta "show the duration between the input and --to (epoch seconds)")
+                    .requires("to"),
+            )
+            .arg(arg!(--to <TS> "end of the duration, in seconds since Epoch").requires("delta"))
             .arg(arg!([value]  "input value, reads from stdin if not present"))
     }
 
@@ -73,18 +144,34 @@ impl Applet for TimeApplet {
         Box::new(Self {
             local: false,
             verbose: false,
+            delta_to: None,
         })
     }
 
     fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let delta_to = if args.get_flag("delta") {
+            Some(
+                i64::from_str_with_radix(args.get_one::<String>("to").unwrap())
+                    .with_context(|| "Invalid --to timestamp")?,
+            )
+        } else {
+            None
+        };
         Ok(Box::new(Self {
             local: args.get_flag("local"),
             verbose: args.get_flag("verbose"),
+            delta_to,
         }))
     }
 
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
         let ts_str = String::from_utf8(val).unwrap();
+        if let Some(to) = self.delta_to {
+            let from = i64::from_str_with_radix(ts_str.trim())
+                .with_context(|| "Invalid input timestamp")?;
+            let delta = to.checked_sub(from).with_context(|| "Duration overflow")?;
+            return Ok(format_duration(delta).as_bytes().to_vec());
+        }
         let ts_int = i64::from_str_with_radix(ts_str.as_str()).unwrap();
         let ts_len = if !ts_str.starts_with("0x") {
             // if the string is in decimal, return the number of digits
@@ -152,6 +239,84 @@ impl Applet for TimeApplet {
     }
 }
 
+pub struct FiletimeApplet {
+    reverse: bool,
+    dos: bool,
+}
+
+impl Applet for FiletimeApplet {
+    fn command(&self) -> &'static str {
+        "filetime"
+    }
+    fn description(&self) -> &'static str {
+        "convert Windows FILETIME or DOS timestamps to UTC dates"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-r --reverse "convert a date to a timestamp"))
+            .arg(arg!(--dos "32-bit DOS date (high word) and time (low word), as in FAT and ZIP"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Timestamps can be given in decimal, or in hex with a 0x prefix.\n\
+                 FILETIME dates are in RFC 3339 format: 2020-01-01T00:00:00Z.\n\
+                 DOS dates have no time zone: 2020-01-01T00:00:00, and are output in hex \
+                 when reversing.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            reverse: false,
+            dos: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            reverse: args.get_flag("reverse"),
+            dos: args.get_flag("dos"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let s = String::from_utf8_lossy(&val).trim().to_string();
+        let dos_format =
+            format_description::parse("[year]-[month]-[day]T[hour]:[minute]:[second]").unwrap();
+        let res = match (self.dos, self.reverse) {
+            (false, false) => {
+                let ts = i64::from_str_with_radix(&s)
+                    .with_context(|| format!("Invalid FILETIME \"{}\"", s))?;
+                decode_windows_filetime(ts)?
+                    .format(&format_description::well_known::Rfc3339)
+                    .context("Date formatting failed")?
+            }
+            (false, true) => {
+                let date = OffsetDateTime::parse(&s, &format_description::well_known::Rfc3339)
+                    .with_context(|| format!("Invalid RFC 3339 date \"{}\"", s))?;
+                encode_windows_filetime(date).to_string()
+            }
+            (true, false) => {
+                let ts = u32::try_from(
+                    u64::from_str_with_radix(&s)
+                        .with_context(|| format!("Invalid DOS timestamp \"{}\"", s))?,
+                )
+                .context("DOS timestamps are 32-bit")?;
+                decode_dos_datetime(ts)?
+                    .format(&dos_format)
+                    .context("Date formatting failed")?
+            }
+            (true, true) => {
+                let date = PrimitiveDateTime::parse(&s, &dos_format)
+                    .with_context(|| format!("Invalid date \"{}\"", s))?;
+                format!("0x{:08x}", encode_dos_datetime(date)?)
+            }
+        };
+        Ok(res.into_bytes())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,7 +329,7 @@ mod tests {
     fn test_verbose_cli_stdin() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["tsdec", "-v"])
+            .args(["tsdec", "-v"])
             .write_stdin("1")
             .assert()
             .stdout("1970-01-01T00:00:01Z")
@@ -177,6 +342,7 @@ mod tests {
         let ts = TimeApplet {
             local: false,
             verbose: false,
+            delta_to: None,
         };
         assert_eq!(run_decode(&ts, "0"), "1970-01-01T00:00:00Z");
         assert_eq!(run_decode(&ts, "1420070400"), "2015-01-01T00:00:00Z");
@@ -204,8 +370,124 @@ mod tests {
         let ts = TimeApplet {
             local: false,
             verbose: false,
+            delta_to: None,
         };
         assert_eq!(run_decode(&ts, "0x0"), "1970-01-01T00:00:00Z");
         assert_eq!(run_decode(&ts, "0x1"), "1970-01-01T00:00:01Z");
     }
+
+    #[test]
+    fn test_delta_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["tsdec", "--delta", "--to", "1735689600", "1420070400"])
+            .assert()
+            .stdout("3653d")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["tsdec", "-l", "--delta", "--to", "1735689600", "1420070400"])
+            .assert()
+            .stderr(predicates::str::contains("cannot be used with"))
+            .failure();
+    }
+
+    #[test]
+    fn test_delta() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(3 * 86400 + 4 * 3600 + 12 * 60), "3d 4h 12m");
+        assert_eq!(format_duration(3661), "1h 1m 1s");
+        assert_eq!(format_duration(-90), "-1m 30s");
+
+        let ts = TimeApplet {
+            local: false,
+            verbose: false,
+            delta_to: Some(100),
+        };
+        assert_eq!(run_decode(&ts, "0x10\n"), "1m 24s");
+        assert_eq!(run_decode(&ts, "200"), "-1m 40s");
+    }
+
+    #[test]
+    fn test_filetime_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["filetime", "132223104000000000"])
+            .assert()
+            .stdout("2020-01-01T00:00:00Z")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["filetime", "--dos", "0x4f5c6c6e"])
+            .assert()
+            .stdout("2019-10-28T13:35:28")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["filetime", "--", "-9223372036854775807"])
+            .assert()
+            .stderr(predicates::str::contains("Out of range FILETIME"))
+            .failure();
+    }
+
+    #[test]
+    fn test_filetime() {
+        let run = |reverse: bool, dos: bool, val: &str| {
+            let app = FiletimeApplet { reverse, dos };
+            app.process(val.as_bytes().to_vec())
+                .map(|r| String::from_utf8(r).unwrap())
+        };
+        assert_eq!(run(false, false, "0").unwrap(), "1601-01-01T00:00:00Z");
+        assert_eq!(
+            run(false, false, "116444736000000000\n").unwrap(),
+            "1970-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            run(false, false, "0x1d5c03669050001").unwrap(),
+            "2020-01-01T00:00:00.0000001Z"
+        );
+        assert_eq!(
+            run(true, false, "2020-01-01T00:00:00Z").unwrap(),
+            "132223104000000000"
+        );
+        assert_eq!(
+            run(true, false, "2020-01-01T01:00:00.0000001+01:00").unwrap(),
+            "132223104000000001"
+        );
+        assert_eq!(run(true, false, "1601-01-01T00:00:00Z").unwrap(), "0");
+        assert_eq!(
+            run(true, false, "1600-12-31T23:59:59.99999999Z").unwrap(),
+            "-1"
+        );
+        assert!(run(false, false, "toto").is_err());
+        // extreme values
+        assert!(run(false, false, &i64::MIN.to_string()).is_err());
+        assert!(run(false, false, &(i64::MIN + 1).to_string()).is_err());
+        assert!(run(false, false, &i64::MAX.to_string()).is_err());
+        assert!(run(true, false, "2020-01-01").is_err());
+    }
+
+    #[test]
+    fn test_dos() {
+        let run = |reverse: bool, val: &str| {
+            let app = FiletimeApplet { reverse, dos: true };
+            app.process(val.as_bytes().to_vec())
+                .map(|r| String::from_utf8(r).unwrap())
+        };
+        assert_eq!(run(false, "0x00210000").unwrap(), "1980-01-01T00:00:00");
+        assert_eq!(run(true, "2019-10-28T13:35:28").unwrap(), "0x4f5c6c6e");
+        // 2 seconds resolution
+        assert_eq!(run(true, "2019-10-28T13:35:29").unwrap(), "0x4f5c6c6e");
+        assert_eq!(run(true, "2107-12-31T23:59:58").unwrap(), "0xff9fbf7d");
+        assert_eq!(run(false, "0xff9fbf7d").unwrap(), "2107-12-31T23:59:58");
+
+        // month 0, hour 24, out of range
+        assert!(run(false, "0").is_err());
+        assert!(run(false, "0x0021c000").is_err());
+        assert!(run(false, "0x100000000").is_err());
+        assert!(run(true, "1979-12-31T23:59:59").is_err());
+    }
 }
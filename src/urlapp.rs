@@ -1,11 +1,13 @@
 use crate::applet::Applet;
-use crate::applet::SliceExt;
-use anyhow::Result;
+use crate::applet::{parse_byte_set, SliceExt};
+use anyhow::{bail, Result};
 use clap::{arg, Command};
 
 pub struct UrlEncApplet {
     // true: should be encoded
     table: [bool; 256],
+    // for explain
+    excluded: String,
 }
 
 // Encoding table according to RFC 3986
@@ -77,6 +79,7 @@ impl Applet for UrlEncApplet {
     fn new() -> Box<dyn Applet> {
         Box::new(Self {
             table: [false; 256],
+            excluded: String::new(),
         })
     }
 
@@ -109,7 +112,35 @@ impl Applet for UrlEncApplet {
         } else {
             build_default_table(excluded, &mut table);
         };
-        Ok(Box::new(Self { table }))
+        Ok(Box::new(Self {
+            table,
+            excluded: excluded.to_string(),
+        }))
+    }
+
+    fn explain(&self) -> String {
+        let graphic = |i: usize| (i as u8).is_ascii_graphic();
+        let encoded: String = (0..256)
+            .filter(|i| graphic(*i) && self.table[*i])
+            .map(|i| i as u8 as char)
+            .collect();
+        let others = (0..256).filter(|i| !graphic(*i) && self.table[*i]).count();
+        let non_graphic = (0..256).filter(|i| !graphic(*i)).count();
+        let mut res = format!(
+            "urlenc: replace bytes with %xx. Encoded ASCII characters: \"{}\".\n",
+            encoded
+        );
+        res.push_str(if others == 0 {
+            "Other bytes are copied as is."
+        } else if others == non_graphic {
+            "Space, control and non ASCII bytes are encoded."
+        } else {
+            "Some space, control or non ASCII bytes are encoded."
+        });
+        if !self.excluded.is_empty() {
+            res.push_str(&format!("\nExcluded from encoding: \"{}\".", self.excluded));
+        }
+        res
     }
 
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
@@ -124,9 +155,90 @@ impl Applet for UrlEncApplet {
         }
         Ok(encoded)
     }
+
+    fn returns_text(&self) -> bool {
+        true
+    }
+}
+
+pub struct UrlDecApplet {
+    trim_bytes: Option<Vec<u8>>,
+    percent_u: bool,
+    strict: bool,
 }
 
-pub struct UrlDecApplet {}
+impl UrlDecApplet {
+    /* Value of the hex digits at the start of val, if all len of them are valid */
+    fn hex_at(val: &[u8], len: usize) -> Option<u32> {
+        let digits = std::str::from_utf8(val.get(..len)?).ok()?;
+        if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    /* Decode a "%uXXXX" sequence at the start of val, combining UTF-16 surrogate
+     * pairs. Returns the decoded char and the number of bytes consumed. */
+    fn decode_percent_u(val: &[u8]) -> Option<(char, usize)> {
+        let unit = Self::decode_percent_u_unit(val)?;
+        match unit {
+            0xD800..=0xDBFF => {
+                let low = Self::decode_percent_u_unit(&val[6..])?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return None;
+                }
+                let code = 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00);
+                Some((char::from_u32(code)?, 12))
+            }
+            _ => Some((char::from_u32(unit)?, 6)),
+        }
+    }
+
+    fn decode_percent_u_unit(val: &[u8]) -> Option<u32> {
+        if val.starts_with(b"%u") || val.starts_with(b"%U") {
+            Self::hex_at(&val[2..], 4)
+        } else {
+            None
+        }
+    }
+
+    fn decode(&self, val: &[u8]) -> Result<Vec<u8>> {
+        let mut res = Vec::with_capacity(val.len());
+        let mut i = 0;
+        while i < val.len() {
+            if val[i] != b'%' {
+                res.push(val[i]);
+                i += 1;
+                continue;
+            }
+            if let Some(b) = Self::hex_at(&val[i + 1..], 2) {
+                res.push(b as u8);
+                i += 3;
+                continue;
+            }
+            if self.percent_u && matches!(val.get(i + 1), Some(b'u') | Some(b'U')) {
+                if let Some((c, len)) = Self::decode_percent_u(&val[i..]) {
+                    let mut buf = [0; 4];
+                    res.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    i += len;
+                    continue;
+                }
+            }
+            if self.strict {
+                let end = val.len().min(i + 6);
+                bail!(
+                    "Invalid percent-encoded sequence at offset {}: \"{}\"",
+                    i,
+                    String::from_utf8_lossy(&val[i..end])
+                );
+            }
+            // lenient: keep invalid sequences as is
+            res.push(b'%');
+            i += 1;
+        }
+        Ok(res)
+    }
+}
 
 impl Applet for UrlDecApplet {
     fn command(&self) -> &'static str {
@@ -137,16 +249,44 @@ impl Applet for UrlDecApplet {
     }
 
     fn new() -> Box<dyn Applet> {
-        Box::new(Self {})
+        Box::new(Self {
+            trim_bytes: None,
+            percent_u: false,
+            strict: false,
+        })
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(--"trim-bytes" <HEXLIST> "bytes to trim from the input instead of whitespace, in hex (ex: 2227)"))
+            .arg(arg!(-u --iis "also decode IIS-style %uXXXX (UTF-16) sequences to UTF-8").visible_alias("percent-u"))
+            .arg(arg!(-s --strict "error on invalid percent-encoded sequences"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help("By default, invalid sequences are copied as is.")
     }
 
-    fn parse_args(&self, _args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
-        Ok(Box::new(Self {}))
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let trim_bytes = match args.get_one::<String>("trim-bytes") {
+            Some(list) => Some(parse_byte_set(list)?),
+            None => None,
+        };
+        Ok(Box::new(Self {
+            trim_bytes,
+            percent_u: args.get_flag("iis"),
+            strict: args.get_flag("strict"),
+        }))
     }
 
     fn process(&self, urlval: Vec<u8>) -> Result<Vec<u8>> {
-        let trimmed: Vec<u8> = urlval.trim().into();
-        let decoded: Vec<u8> = percent_encoding::percent_decode(&trimmed).collect();
+        let trimmed = match &self.trim_bytes {
+            Some(set) => urlval.trim_bytes(set),
+            None => urlval.trim(),
+        };
+        if self.percent_u || self.strict {
+            return self.decode(trimmed);
+        }
+        let decoded: Vec<u8> = percent_encoding::percent_decode(trimmed).collect();
         Ok(decoded)
     }
 }
@@ -159,7 +299,7 @@ mod tests {
     fn test_urlenc_cli_arg() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["urlenc", "aAé!,"])
+            .args(["urlenc", "aAé!,"])
             .assert()
             .stdout("aA%c3%a9%21%2c")
             .success();
@@ -169,7 +309,7 @@ mod tests {
     fn test_urlenc_cli_arg_exclude() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["urlenc", "-e", "!,", "aAé!,"])
+            .args(["urlenc", "-e", "!,", "aAé!,"])
             .assert()
             .stdout("aA%c3%a9!,")
             .success();
@@ -179,7 +319,7 @@ mod tests {
     fn test_urlenc_cli_arg_custom() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["urlenc", "-e", "!,", "-c", "aA,", "aAé!,"])
+            .args(["urlenc", "-e", "!,", "-c", "aA,", "aAé!,"])
             .assert()
             .stdout("%61%41é!,")
             .success();
@@ -189,18 +329,35 @@ mod tests {
     fn test_urlenc_stdin() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["urlenc"])
+            .args(["urlenc"])
             .write_stdin("aAé!,")
             .assert()
             .stdout("aA%c3%a9%21%2c")
             .success();
     }
 
+    #[test]
+    fn test_urlenc_explain_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["urlenc", "--explain", "--rfc3986", "-e", "!,", "abc"])
+            .assert()
+            .stdout(
+                "urlenc: replace bytes with %xx. Encoded ASCII characters: \"#$%&'()*+/:;=?@[]\".\n\
+                 Space, control and non ASCII bytes are encoded.\n\
+                 Excluded from encoding: \"!,\".\n",
+            )
+            .success();
+    }
+
     #[test]
     fn test_urlenc() {
         let mut table = [false; 256];
-        build_default_table(&"".to_string(), &mut table);
-        let urlenc = UrlEncApplet { table: table };
+        build_default_table("", &mut table);
+        let urlenc = UrlEncApplet {
+            table,
+            excluded: String::new(),
+        };
         let encoded = urlenc
             .process("aA!,é".as_bytes().to_vec())
             .expect("encoding failed");
@@ -210,8 +367,11 @@ mod tests {
     #[test]
     fn test_urlenc_00_ff() {
         let mut table = [false; 256];
-        build_default_table(&"".to_string(), &mut table);
-        let urlenc = UrlEncApplet { table: table };
+        build_default_table("", &mut table);
+        let urlenc = UrlEncApplet {
+            table,
+            excluded: String::new(),
+        };
         let encoded = urlenc.process([0, 0xFF].to_vec()).expect("encoding failed");
         assert_eq!(String::from_utf8(encoded).unwrap(), "%00%ff");
     }
@@ -219,9 +379,16 @@ mod tests {
     #[test]
     fn test_urlencdec() {
         let mut table = [false; 256];
-        build_default_table(&"".to_string(), &mut table);
-        let urlenc = UrlEncApplet { table: table };
-        let urldec = UrlDecApplet {};
+        build_default_table("", &mut table);
+        let urlenc = UrlEncApplet {
+            table,
+            excluded: String::new(),
+        };
+        let urldec = UrlDecApplet {
+            trim_bytes: None,
+            percent_u: false,
+            strict: false,
+        };
         let test_string = "aA!,é";
         let encoded = urlenc
             .process(test_string.as_bytes().to_vec())
@@ -229,4 +396,48 @@ mod tests {
         let decoded = urldec.process(encoded).expect("decoding failed");
         assert_eq!(String::from_utf8(decoded).unwrap(), test_string);
     }
+
+    #[test]
+    fn test_urldec_trim_bytes() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["urldec", "--trim-bytes", "22,27", "\"'%41%20B'\""])
+            .assert()
+            .stdout("A B")
+            .success();
+    }
+
+    #[test]
+    fn test_urldec_percent_u() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["urldec", "--iis", "%u0041%42%u00e9"])
+            .assert()
+            .stdout("ABé")
+            .success();
+
+        let lenient = UrlDecApplet {
+            trim_bytes: None,
+            percent_u: true,
+            strict: false,
+        };
+        assert_eq!(lenient.process_test(b"%u0041".to_vec()), b"A");
+        assert_eq!(
+            lenient.process_test(b"%uD83D%uDE00".to_vec()),
+            "\u{1F600}".as_bytes()
+        );
+        assert_eq!(lenient.process_test(b"%u00zz%u".to_vec()), b"%u00zz%u");
+        assert_eq!(lenient.process_test(b"%uD83Dx".to_vec()), b"%uD83Dx");
+        assert_eq!(lenient.process_test(b"100%".to_vec()), b"100%");
+
+        let strict = UrlDecApplet {
+            trim_bytes: None,
+            percent_u: true,
+            strict: true,
+        };
+        assert_eq!(strict.process_test(b"%u0041%41".to_vec()), b"AA");
+        assert!(strict.process(b"%u00zz".to_vec()).is_err());
+        assert!(strict.process(b"%uDE00".to_vec()).is_err());
+        assert!(strict.process(b"100%".to_vec()).is_err());
+    }
 }
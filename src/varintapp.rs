@@ -0,0 +1,226 @@
+use crate::applet::{Applet, FromStrWithRadix, SliceExt};
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+
+/// Encode `val` as unsigned LEB128 (protobuf varint).
+pub fn encode_uleb128(mut val: u64) -> Vec<u8> {
+    let mut res = vec![];
+    loop {
+        let b = (val & 0x7f) as u8;
+        val >>= 7;
+        if val == 0 {
+            res.push(b);
+            return res;
+        }
+        res.push(b | 0x80);
+    }
+}
+
+/// Encode `val` as signed LEB128 (DWARF, WebAssembly).
+pub fn encode_sleb128(mut val: i64) -> Vec<u8> {
+    let mut res = vec![];
+    loop {
+        let b = (val & 0x7f) as u8;
+        val >>= 7;
+        /* Done when the remaining bits are all copies of the sign bit */
+        if (val == 0 && b & 0x40 == 0) || (val == -1 && b & 0x40 != 0) {
+            res.push(b);
+            return res;
+        }
+        res.push(b | 0x80);
+    }
+}
+
+/* Raw 7-bit groups of the varint at the start of val: value, shift, length */
+fn decode_groups(val: &[u8], signed: bool) -> Result<(u64, u32, usize)> {
+    let mut res: u64 = 0;
+    let mut shift = 0;
+    for (i, b) in val.iter().enumerate() {
+        /* The 10th byte only holds the 64th bit, or its sign extension */
+        let valid_last = match signed {
+            false => b & 0x7e == 0,
+            true => b & 0x7f == 0 || b & 0x7f == 0x7f,
+        };
+        if shift >= 64 || (shift == 63 && !valid_last) {
+            bail!("Varint overflows 64 bits");
+        }
+        res |= ((b & 0x7f) as u64) << shift;
+        shift += 7;
+        if b & 0x80 == 0 {
+            return Ok((res, shift, i + 1));
+        }
+    }
+    bail!("Truncated varint: last byte has the continuation bit set")
+}
+
+/// Decode the unsigned LEB128 at the start of `val`, returns the value and
+/// the number of bytes used.
+pub fn decode_uleb128(val: &[u8]) -> Result<(u64, usize)> {
+    let (res, _, len) = decode_groups(val, false)?;
+    Ok((res, len))
+}
+
+/// Decode the signed LEB128 at the start of `val`, returns the value and
+/// the number of bytes used.
+pub fn decode_sleb128(val: &[u8]) -> Result<(i64, usize)> {
+    let (mut res, shift, len) = decode_groups(val, true)?;
+    if shift < 64 && val[len - 1] & 0x40 != 0 {
+        res |= u64::MAX << shift;
+    }
+    Ok((res as i64, len))
+}
+
+pub struct VarintApplet {
+    reverse: bool,
+    signed: bool,
+}
+
+impl Applet for VarintApplet {
+    fn command(&self) -> &'static str {
+        "varint"
+    }
+    fn description(&self) -> &'static str {
+        "encode an integer as LEB128 varint"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(arg!(-r --reverse "decode varints to integers, one per line"))
+            .arg(arg!(-s --signed "signed LEB128 (DWARF, WebAssembly) instead of unsigned"))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "Integers can be given in decimal, or in hex/octal with a 0x/0o prefix.\n\
+                 When decoding, all the varints of the input are decoded.",
+            )
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            reverse: false,
+            signed: false,
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        Ok(Box::new(Self {
+            reverse: args.get_flag("reverse"),
+            signed: args.get_flag("signed"),
+        }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        if self.reverse {
+            let mut res = String::new();
+            let mut off = 0;
+            while off < val.len() {
+                let len = if self.signed {
+                    let (v, len) = decode_sleb128(&val[off..])?;
+                    res.push_str(&format!("{}\n", v));
+                    len
+                } else {
+                    let (v, len) = decode_uleb128(&val[off..])?;
+                    res.push_str(&format!("{}\n", v));
+                    len
+                };
+                off += len;
+            }
+            return Ok(res.into_bytes());
+        }
+
+        let s = String::from_utf8_lossy(val.trim()).to_string();
+        if self.signed {
+            let num = match s.strip_prefix('-') {
+                Some(abs) => u64::from_str_with_radix(abs)
+                    .ok()
+                    .and_then(|v| 0i64.checked_sub_unsigned(v)),
+                None => i64::from_str_with_radix(&s).ok(),
+            }
+            .with_context(|| format!("Invalid signed 64-bit integer \"{}\"", s))?;
+            Ok(encode_sleb128(num))
+        } else {
+            let num = u64::from_str_with_radix(&s)
+                .with_context(|| format!("Invalid unsigned 64-bit integer \"{}\"", s))?;
+            Ok(encode_uleb128(num))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["varint", "300"])
+            .assert()
+            .stdout(predicates::ord::eq(b"\xac\x02".as_slice()))
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["varint", "-r", "-s"])
+            .write_stdin(b"\xc0\xbb\x78\x7f".as_slice())
+            .assert()
+            .stdout("-123456\n-1\n")
+            .success();
+    }
+
+    #[test]
+    fn test_unsigned() {
+        let enc = VarintApplet {
+            reverse: false,
+            signed: false,
+        };
+        // one, two and three bytes
+        assert_eq!(enc.process_test(b"1".to_vec()), [0x01]);
+        assert_eq!(enc.process_test(b"127".to_vec()), [0x7f]);
+        assert_eq!(enc.process_test(b"128".to_vec()), [0x80, 0x01]);
+        assert_eq!(enc.process_test(b"0x12c".to_vec()), [0xac, 0x02]);
+        assert_eq!(enc.process_test(b"624485\n".to_vec()), [0xe5, 0x8e, 0x26]);
+        assert_eq!(encode_uleb128(u64::MAX).len(), 10);
+        assert!(enc.process(b"-1".to_vec()).is_err());
+
+        let dec = VarintApplet {
+            reverse: true,
+            signed: false,
+        };
+        assert_eq!(
+            dec.process_test(vec![0x01, 0x80, 0x01, 0xe5, 0x8e, 0x26]),
+            b"1\n128\n624485\n"
+        );
+        assert_eq!(
+            decode_uleb128(&encode_uleb128(u64::MAX)).unwrap(),
+            (u64::MAX, 10)
+        );
+        // truncated, overflow
+        assert!(dec.process(vec![0xe5, 0x8e]).is_err());
+        assert!(decode_uleb128(&[0xff; 10]).is_err());
+        assert!(
+            decode_uleb128(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_signed() {
+        let enc = VarintApplet {
+            reverse: false,
+            signed: true,
+        };
+        assert_eq!(enc.process_test(b"-1".to_vec()), [0x7f]);
+        assert_eq!(enc.process_test(b"63".to_vec()), [0x3f]);
+        assert_eq!(enc.process_test(b"64".to_vec()), [0xc0, 0x00]);
+        assert_eq!(enc.process_test(b"-0x80".to_vec()), [0x80, 0x7f]);
+        assert_eq!(enc.process_test(b"-123456".to_vec()), [0xc0, 0xbb, 0x78]);
+
+        for v in [0, 1, -1, 64, -65, 8191, -8193, i64::MIN, i64::MAX] {
+            let b = encode_sleb128(v);
+            assert_eq!(decode_sleb128(&b).unwrap(), (v, b.len()));
+        }
+        let min = format!("{}", i64::MIN).into_bytes();
+        assert_eq!(enc.process_test(min).len(), 10);
+        assert!(decode_sleb128(&[0x80, 0x80]).is_err());
+    }
+}
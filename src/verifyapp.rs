@@ -0,0 +1,174 @@
+use crate::applet::Applet;
+use anyhow::{bail, Context, Result};
+use clap::{arg, Command};
+use sha2::Digest;
+
+#[derive(clap::ValueEnum, Clone, Copy, Default, Debug)]
+enum HashAlgo {
+    Md5,
+    Sha1,
+    Sha224,
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgo {
+    fn digest(&self, val: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgo::Md5 => md5::Md5::digest(val).to_vec(),
+            HashAlgo::Sha1 => sha1::Sha1::digest(val).to_vec(),
+            HashAlgo::Sha224 => sha2::Sha224::digest(val).to_vec(),
+            HashAlgo::Sha256 => sha2::Sha256::digest(val).to_vec(),
+            HashAlgo::Sha384 => sha2::Sha384::digest(val).to_vec(),
+            HashAlgo::Sha512 => sha2::Sha512::digest(val).to_vec(),
+        }
+    }
+
+    fn digest_len(&self) -> usize {
+        match self {
+            HashAlgo::Md5 => 16,
+            HashAlgo::Sha1 => 20,
+            HashAlgo::Sha224 => 28,
+            HashAlgo::Sha256 => 32,
+            HashAlgo::Sha384 => 48,
+            HashAlgo::Sha512 => 64,
+        }
+    }
+}
+
+/* Compare without early exit, so timing does not depend on the
+ * position of the first difference */
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+pub struct VerifyApplet {
+    algo: HashAlgo,
+    expected: Vec<u8>,
+}
+
+impl Applet for VerifyApplet {
+    fn command(&self) -> &'static str {
+        "verify"
+    }
+    fn description(&self) -> &'static str {
+        "check the hash of the input"
+    }
+
+    fn clap_command(&self) -> Command {
+        Command::new(self.command())
+            .about(self.description())
+            .arg(
+                arg!(-a --algo <algo> "hash algorithm")
+                    .value_parser(clap::builder::EnumValueParser::<HashAlgo>::new())
+                    .default_value("sha256"),
+            )
+            .arg(arg!(-e --expected <HEX> "expected digest, in hex").required(true))
+            .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help("Outputs \"OK\" if the digest matches, fails otherwise.")
+    }
+
+    fn new() -> Box<dyn Applet> {
+        Box::new(Self {
+            algo: HashAlgo::Sha256,
+            expected: vec![],
+        })
+    }
+
+    fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        let algo = *args.get_one::<HashAlgo>("algo").unwrap();
+        let expected = hex::decode(
+            args.get_one::<String>("expected")
+                .unwrap()
+                .trim()
+                .replace(' ', ""),
+        )
+        .with_context(|| "Invalid expected digest")?;
+        if expected.len() != algo.digest_len() {
+            bail!(
+                "Expected digest is {} bytes long, {:?} digests are {} bytes long",
+                expected.len(),
+                algo,
+                algo.digest_len()
+            );
+        }
+        Ok(Box::new(Self { algo, expected }))
+    }
+
+    fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
+        let digest = self.algo.digest(&val);
+        if !constant_time_eq(&digest, &self.expected) {
+            bail!(
+                "Digest mismatch: expected {}, got {}",
+                hex::encode(&self.expected),
+                hex::encode(&digest)
+            );
+        }
+        Ok(b"OK".to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256_ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_cli() {
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["verify", "-e", &SHA256_ABC.to_uppercase()])
+            .write_stdin("abc")
+            .assert()
+            .stdout("OK")
+            .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["verify", "-e", SHA256_ABC])
+            .write_stdin("abd")
+            .assert()
+            .stdout("")
+            .stderr(predicates::str::contains("Digest mismatch"))
+            .failure();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["verify", "-a", "md5", "-e", SHA256_ABC, "abc"])
+            .assert()
+            .stderr(predicates::str::contains("is 32 bytes long"))
+            .failure();
+    }
+
+    #[test]
+    fn test_verify() {
+        let verify = VerifyApplet {
+            algo: HashAlgo::Md5,
+            expected: hex::decode("900150983cd24fb0d6963f7d28e17f72").unwrap(),
+        };
+        assert_eq!(verify.process_test(b"abc".to_vec()), b"OK");
+        assert!(verify.process(b"abc\n".to_vec()).is_err());
+
+        let verify = VerifyApplet {
+            algo: HashAlgo::Sha1,
+            expected: hex::decode("a9993e364706816aba3e25717850c26c9cd0d89d").unwrap(),
+        };
+        assert_eq!(verify.process_test(b"abc".to_vec()), b"OK");
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
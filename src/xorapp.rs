@@ -1,10 +1,55 @@
 use crate::applet::Applet;
-use anyhow::{Context, Result};
-use clap::{arg, Command};
+use crate::hammingapp::hamming_distance;
+use anyhow::{bail, Context, Result};
+use clap::{arg, value_parser, Command};
 use std::fs;
+use std::io::{Read, Write};
+
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
 
 pub struct XorApplet {
     key_bytes: Vec<u8>,
+    /* max keysize and number of results, for keysize guessing */
+    guess_keysize: Option<(usize, usize)>,
+}
+
+impl XorApplet {
+    /* Xor data in place, starting at key_off in the key */
+    fn xor(&self, data: &mut [u8], key_off: usize) {
+        let inf_key = self.key_bytes.iter().cycle().skip(key_off); // Iterate endlessly over key bytes
+        for (x, k) in data.iter_mut().zip(inf_key) {
+            *x ^= k;
+        }
+    }
+
+    /* Normalized Hamming distance between successive blocks of keysize bytes.
+     * The lowest scores are the most probable keysizes for repeating-key xor */
+    fn keysize_score(val: &[u8], keysize: usize) -> f64 {
+        let blocks: Vec<&[u8]> = val.chunks_exact(keysize).collect();
+        let pairs = blocks.len() - 1;
+        let total: u64 = blocks
+            .windows(2)
+            .map(|w| hamming_distance(w[0], w[1]))
+            .sum();
+        total as f64 / (pairs * keysize) as f64
+    }
+
+    fn guess_keysize(&self, val: &[u8], max: usize, top: usize) -> Result<Vec<u8>> {
+        let max = max.min(val.len() / 2);
+        if max == 0 {
+            bail!("Input is too short to guess the keysize");
+        }
+        let mut scores: Vec<(usize, f64)> = (1..=max)
+            .map(|k| (k, Self::keysize_score(val, k)))
+            .collect();
+        scores.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let res: String = scores
+            .iter()
+            .take(top)
+            .map(|(k, score)| format!("{}: {:.3}\n", k, score))
+            .collect();
+        Ok(res.into_bytes())
+    }
 }
 
 impl Applet for XorApplet {
@@ -20,18 +65,48 @@ impl Applet for XorApplet {
             .about(self.description())
             .arg(
                 arg!(-x --xorkey <KEY>  "Xor key in hex format")
-                    .required_unless_present("keyfile")
+                    .required_unless_present_any(["keyfile", "guess-keysize"])
                     .conflicts_with("keyfile"),
             )
             .arg(arg!(-f --keyfile <keyfile>  "File to use as key"))
+            .arg(
+                arg!(-g --"guess-keysize" "rank the probable key sizes of repeating-key xored input")
+                    .conflicts_with_all(["xorkey", "keyfile"]),
+            )
+            .arg(
+                arg!(--"max-keysize" <N> "largest key size to try when guessing")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .default_value("40"),
+            )
+            .arg(
+                arg!(--top <N> "number of key sizes to output when guessing")
+                    .value_parser(value_parser!(u64).range(1..))
+                    .default_value("5"),
+            )
             .arg(arg!([value]  "input value, reads from stdin if not present"))
+            .after_help(
+                "--guess-keysize outputs \"keysize: score\" lines, best first. The score is \
+                 the Hamming distance between successive blocks, normalized by the keysize.",
+            )
     }
 
     fn new() -> Box<dyn Applet> {
-        Box::new(Self { key_bytes: vec![] })
+        Box::new(Self {
+            key_bytes: vec![],
+            guess_keysize: None,
+        })
     }
 
     fn parse_args(&self, args: &clap::ArgMatches) -> Result<Box<dyn Applet>> {
+        if args.get_flag("guess-keysize") {
+            return Ok(Box::new(Self {
+                key_bytes: vec![],
+                guess_keysize: Some((
+                    *args.get_one::<u64>("max-keysize").unwrap() as usize,
+                    *args.get_one::<u64>("top").unwrap() as usize,
+                )),
+            }));
+        }
         let key_bytes = if args.contains_id("xorkey") {
             hex::decode(args.get_one::<String>("xorkey").unwrap().replace(' ', ""))
                 .with_context(|| "Xor key decoding failed")?
@@ -39,28 +114,92 @@ impl Applet for XorApplet {
             fs::read(args.get_one::<String>("keyfile").unwrap())
                 .with_context(|| "Could not read keyfile")?
         };
-        Ok(Box::new(Self { key_bytes }))
+        if key_bytes.is_empty() {
+            bail!("Empty xor key");
+        }
+        Ok(Box::new(Self {
+            key_bytes,
+            guess_keysize: None,
+        }))
     }
 
     fn process(&self, val: Vec<u8>) -> Result<Vec<u8>> {
-        let inf_key = self.key_bytes.iter().cycle(); // Iterate endlessly over key bytes
-        Ok(val.iter().zip(inf_key).map(|(x, k)| x ^ k).collect())
+        if let Some((max, top)) = self.guess_keysize {
+            return self.guess_keysize(&val, max, top);
+        }
+        let mut val = val;
+        self.xor(&mut val, 0);
+        Ok(val)
+    }
+
+    fn explain(&self) -> String {
+        match self.guess_keysize {
+            Some((max, top)) => format!(
+                "xor: rank key sizes from 1 to {} of repeating-key xored input, output the {} best.",
+                max, top
+            ),
+            None => format!(
+                "xor: xor the input with the {} bytes key {}, repeated as needed.",
+                self.key_bytes.len(),
+                hex::encode(&self.key_bytes)
+            ),
+        }
+    }
+
+    /* Xor chunk by chunk, keeping track of the key offset */
+    fn process_stream(&self, input: &mut dyn Read, output: &mut dyn Write) -> Result<()> {
+        if self.guess_keysize.is_some() {
+            let mut val = Vec::new();
+            input
+                .read_to_end(&mut val)
+                .with_context(|| "Reading input failed")?;
+            output.write_all(&self.process(val)?)?;
+            return Ok(());
+        }
+        let mut buf = vec![0; STREAM_CHUNK_LEN];
+        let mut key_off = 0;
+        loop {
+            let len = match input.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(len) => len,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e).with_context(|| "Reading input failed"),
+            };
+            self.xor(&mut buf[..len], key_off);
+            key_off = (key_off + len) % self.key_bytes.len();
+            output.write_all(&buf[..len])?;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::applet::check_stream;
     use std::io::Write;
 
     #[test]
     fn test_hex_key_cli() {
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["xor", "-x", "41", "AAAA"])
+            .args(["xor", "-x", "41", "AAAA"])
             .assert()
             .stdout(&b"\0\0\0\0"[..])
             .success();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["xor", "-x", "", "AAAA"])
+            .assert()
+            .stderr(predicates::str::contains("Empty xor key"))
+            .failure();
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["xor", "-x", "4142", "--explain"])
+            .assert()
+            .stdout("xor: xor the input with the 2 bytes key 4142, repeated as needed.\n")
+            .success();
     }
 
     #[test]
@@ -72,11 +211,11 @@ mod tests {
         let zero_data = [0u8; 32];
 
         let mut tmpkey = tempfile::NamedTempFile::new().unwrap();
-        tmpkey.write(&rand_key.clone()).unwrap();
+        tmpkey.write_all(&rand_key.clone()).unwrap();
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&["xor", "-f", tmpkey.path().to_str().expect("Could not get path as str")])
+            .args(["xor", "-f", tmpkey.path().to_str().expect("Could not get path as str")])
             .write_stdin(zero_data)
             .assert()
             .stdout(&b"\x7fZ\x88{\xE8\x81\xD6^9\xF4~%\xF2\x05\xDC\"\x86\x7fZ\x88{\xE8\x81\xD6^9\xF4~%\xF2\x05\xDC"[..])
@@ -84,7 +223,7 @@ mod tests {
 
         assert_cmd::Command::cargo_bin("rsbkb")
             .expect("Could not run binary")
-            .args(&[
+            .args([
                 "xor",
                 "-f",
                 tmpkey.path().to_str().expect("Could not get path as str"),
@@ -100,6 +239,7 @@ mod tests {
         let data = vec![1, 0x55, 0xAA, 0xFF, 0];
         let x = XorApplet {
             key_bytes: data.clone(),
+            guess_keysize: None,
         };
         assert_eq!(x.process_test(vec![0, 0, 0, 0, 0]), data);
         assert_eq!(
@@ -112,4 +252,49 @@ mod tests {
             vec![1, 0x55, 0xAA, 0xFF, 0, 1]
         );
     }
+
+    #[test]
+    fn test_stream() {
+        let x = XorApplet {
+            key_bytes: b"\x01\x23\x45\x67\x89".to_vec(),
+            guess_keysize: None,
+        };
+        let data: Vec<u8> = (0..STREAM_CHUNK_LEN * 2 + 3)
+            .map(|i| (i * 7) as u8)
+            .collect();
+        // the small chunks do not line up with the key
+        check_stream(&x, &data);
+    }
+
+    #[test]
+    fn test_guess_keysize() {
+        let plain = b"Burning 'em, if you ain't quick and nimble. I go crazy when I hear a \
+            cymbal. The quick brown fox jumps over the lazy dog, and the five boxing \
+            wizards jump quickly. Pack my box with five dozen liquor jugs, how vexingly \
+            quick daft zebras jump! Sphinx of black quartz, judge my vow.";
+        let encrypt = XorApplet {
+            key_bytes: b"SECRETKEY".to_vec(),
+            guess_keysize: None,
+        };
+        let cipher = encrypt.process_test(plain.to_vec());
+
+        let guess = XorApplet {
+            key_bytes: vec![],
+            guess_keysize: Some((20, 3)),
+        };
+        let res = String::from_utf8(guess.process_test(cipher.clone())).unwrap();
+        let lines: Vec<&str> = res.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("9: "), "{}", res);
+
+        assert_cmd::Command::cargo_bin("rsbkb")
+            .expect("Could not run binary")
+            .args(["xor", "-g", "--top", "1"])
+            .write_stdin(cipher)
+            .assert()
+            .stdout(predicates::str::starts_with("9: "))
+            .success();
+
+        assert!(guess.process(b"a".to_vec()).is_err());
+    }
 }